@@ -0,0 +1,170 @@
+use crate::colors::NamedColor;
+
+/// Types of color vision deficiency that can be simulated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorBlindness {
+    /// Missing or defective L-cones (red-blind).
+    Protanopia,
+    /// Missing or defective M-cones (green-blind).
+    Deuteranopia,
+    /// Missing or defective S-cones (blue-blind).
+    Tritanopia,
+}
+
+impl ColorBlindness {
+    /// Simulation matrices from Machado, Oliveira & Fernandes (2009), severity 1.0.
+    /// They operate on linear RGB.
+    fn matrix(&self) -> [[f64; 3]; 3] {
+        match self {
+            ColorBlindness::Protanopia => [
+                [0.152286, 1.052583, -0.204868],
+                [0.114503, 0.786281, 0.099216],
+                [-0.003882, -0.048116, 1.051998],
+            ],
+            ColorBlindness::Deuteranopia => [
+                [0.367322, 0.860646, -0.227968],
+                [0.280085, 0.672501, 0.047413],
+                [-0.011820, 0.042940, 0.968881],
+            ],
+            ColorBlindness::Tritanopia => [
+                [1.255528, -0.076749, -0.178779],
+                [-0.078411, 0.930809, 0.147602],
+                [0.004733, 0.691367, 0.303900],
+            ],
+        }
+    }
+
+    /// Returns how the given color appears to someone with this deficiency.
+    pub fn simulate(&self, color: NamedColor) -> NamedColor {
+        let (r, g, b) = color.to_rgb();
+        let linear = [to_linear(r), to_linear(g), to_linear(b)];
+        let m = self.matrix();
+        let mix = |row: [f64; 3]| row[0] * linear[0] + row[1] * linear[1] + row[2] * linear[2];
+        NamedColor::FullColor((
+            from_linear(mix(m[0])),
+            from_linear(mix(m[1])),
+            from_linear(mix(m[2])),
+        ))
+    }
+}
+
+fn to_linear(c: u8) -> f64 {
+    let c = c as f64 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn from_linear(c: f64) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    let s = if c <= 0.0031308 {
+        c * 12.92
+    } else {
+        1.055 * c.powf(1.0 / 2.4) - 0.055
+    };
+    (s * 255.0).round() as u8
+}
+
+/// Substitutes a color with its counterpart from the Okabe-Ito palette,
+/// which stays distinguishable for all common forms of color blindness.
+///
+/// Grays are kept as-is. 256-color and RGB values are matched to the nearest
+/// base color first, so e.g. any reddish shade becomes vermillion.
+pub fn accessible_color(color: NamedColor) -> NamedColor {
+    let base = match color {
+        NamedColor::Code256(_) | NamedColor::FullColor(_) => nearest_base_color(color),
+        other => other,
+    };
+    match base {
+        NamedColor::Red | NamedColor::LightRed => NamedColor::FullColor((213, 94, 0)),
+        NamedColor::Green | NamedColor::LightGreen => NamedColor::FullColor((0, 158, 115)),
+        NamedColor::Yellow | NamedColor::LightYellow => NamedColor::FullColor((240, 228, 66)),
+        NamedColor::Blue | NamedColor::LightBlue => NamedColor::FullColor((0, 114, 178)),
+        NamedColor::Magenta | NamedColor::LightMagenta => NamedColor::FullColor((204, 121, 167)),
+        NamedColor::Cyan | NamedColor::LightCyan => NamedColor::FullColor((86, 180, 233)),
+        _ => color,
+    }
+}
+
+fn nearest_base_color(color: NamedColor) -> NamedColor {
+    const CANDIDATES: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::Red,
+        NamedColor::Green,
+        NamedColor::Yellow,
+        NamedColor::Blue,
+        NamedColor::Magenta,
+        NamedColor::Cyan,
+        NamedColor::White,
+        NamedColor::LightBlack,
+        NamedColor::LightRed,
+        NamedColor::LightGreen,
+        NamedColor::LightYellow,
+        NamedColor::LightBlue,
+        NamedColor::LightMagenta,
+        NamedColor::LightCyan,
+        NamedColor::LightWhite,
+    ];
+    let (r, g, b) = color.to_rgb();
+    let distance = |c: &NamedColor| {
+        let (cr, cg, cb) = c.to_rgb();
+        let d = |a: u8, b: u8| (a as i32 - b as i32).pow(2);
+        d(r, cr) + d(g, cg) + d(b, cb)
+    };
+    CANDIDATES
+        .iter()
+        .min_by_key(|c| distance(c))
+        .copied()
+        .unwrap_or(color)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_grays_survive_simulation() {
+        for kind in [
+            ColorBlindness::Protanopia,
+            ColorBlindness::Deuteranopia,
+            ColorBlindness::Tritanopia,
+        ] {
+            assert_eq!(
+                kind.simulate(NamedColor::FullColor((255, 255, 255))),
+                NamedColor::FullColor((255, 255, 255))
+            );
+            assert_eq!(
+                kind.simulate(NamedColor::Black),
+                NamedColor::FullColor((0, 0, 0))
+            );
+        }
+    }
+
+    #[test]
+    fn test_deuteranopia_collapses_red_and_green() {
+        let kind = ColorBlindness::Deuteranopia;
+        let red = kind.simulate(NamedColor::Red);
+        let green = kind.simulate(NamedColor::Green);
+        let original = NamedColor::Red.contrast_ratio(&NamedColor::Green);
+        assert!(red.contrast_ratio(&green) < original);
+    }
+
+    #[test]
+    fn test_accessible_color_remaps_chromatic_colors() {
+        assert_eq!(
+            accessible_color(NamedColor::Red),
+            NamedColor::FullColor((213, 94, 0))
+        );
+        assert_eq!(
+            accessible_color(NamedColor::Code256(46)),
+            NamedColor::FullColor((0, 158, 115))
+        );
+        assert_eq!(accessible_color(NamedColor::White), NamedColor::White);
+        assert_eq!(
+            accessible_color(NamedColor::Code256(240)),
+            NamedColor::Code256(240)
+        );
+    }
+}
@@ -0,0 +1,61 @@
+//! Plain ANSI SGR backend for terminals, TUIs and anything else that is not a shell prompt.
+
+use crate::context::PromptContext;
+use crate::sequences::{ZshSequence, expand_conditionals};
+
+fn sgr(params: &str) -> String {
+    format!("\x1b[{}m", params)
+}
+
+/// Converts a single sequence, resolving dynamic values from `ctx`.
+pub fn render_sequence(seq: &ZshSequence, ctx: &PromptContext) -> String {
+    match seq {
+        ZshSequence::BoldStart => sgr("1"),
+        ZshSequence::BoldEnd => sgr("22"),
+        ZshSequence::UnderlineStart => sgr("4"),
+        ZshSequence::UnderlineEnd => sgr("24"),
+        ZshSequence::StandoutStart => sgr("7"),
+        ZshSequence::StandoutEnd => sgr("27"),
+        ZshSequence::ForegroundColor(color) => sgr(&color.to_ansi_sgr(false)),
+        ZshSequence::ForegroundColorEnd => sgr("39"),
+        ZshSequence::BackgroundColor(color) => sgr(&color.to_ansi_sgr(true)),
+        ZshSequence::BackgroundColorEnd => sgr("49"),
+        ZshSequence::ResetStyles => sgr("0"),
+        _ => seq.resolve(ctx),
+    }
+}
+
+/// Renders the sequences as text with ANSI SGR escapes.
+pub fn render(sequences: &[ZshSequence], ctx: &PromptContext) -> String {
+    expand_conditionals(sequences, ctx)
+        .iter()
+        .map(|seq| render_sequence(seq, ctx))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NamedColor;
+
+    #[test]
+    fn test_render_ansi() {
+        let ctx = PromptContext {
+            username: "alice".to_string(),
+            ..Default::default()
+        };
+        let output = render(
+            &[
+                ZshSequence::BoldStart,
+                ZshSequence::ForegroundColor(NamedColor::Red),
+                ZshSequence::Username,
+                ZshSequence::ForegroundColorEnd,
+                ZshSequence::Literal(" 100%".to_string()),
+                ZshSequence::BoldEnd,
+                ZshSequence::ResetStyles,
+            ],
+            &ctx,
+        );
+        assert_eq!(output, "\x1b[1m\x1b[31malice\x1b[39m 100%\x1b[22m\x1b[0m");
+    }
+}
@@ -0,0 +1,149 @@
+//! Bash `PS1` backend.
+
+use crate::backends::RenderOutput;
+use crate::sequences::ZshSequence;
+
+/// Wraps an SGR parameter list in bash's non-printing markers.
+fn sgr(params: &str) -> String {
+    format!("\\[\\e[{}m\\]", params)
+}
+
+/// Escapes literal text for `PS1`.
+///
+/// Bash decodes backslash escapes first and then (with `promptvars`, the default)
+/// expands `$` and backticks, so those need an escaped backslash in front.
+/// `!` is the history number in `PS1` and is written as `!!`.
+fn escape_literal(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '\\' => result.push_str("\\\\\\\\"),
+            '$' => result.push_str("\\\\$"),
+            '`' => result.push_str("\\\\`"),
+            '!' => result.push_str("!!"),
+            '\n' => result.push_str("\\n"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+/// Converts a single sequence, returning `None` when bash has no equivalent.
+pub fn render_sequence(seq: &ZshSequence) -> Option<String> {
+    let s = match seq {
+        ZshSequence::Percent => "%".to_string(),
+        ZshSequence::BoldStart => sgr("1"),
+        ZshSequence::BoldEnd => sgr("22"),
+        ZshSequence::UnderlineStart => sgr("4"),
+        ZshSequence::UnderlineEnd => sgr("24"),
+        ZshSequence::StandoutStart => sgr("7"),
+        ZshSequence::StandoutEnd => sgr("27"),
+        ZshSequence::ForegroundColor(color) => sgr(&color.to_ansi_sgr(false)),
+        ZshSequence::ForegroundColorEnd => sgr("39"),
+        ZshSequence::BackgroundColor(color) => sgr(&color.to_ansi_sgr(true)),
+        ZshSequence::BackgroundColorEnd => sgr("49"),
+        ZshSequence::ResetStyles => sgr("0"),
+        ZshSequence::Username => "\\u".to_string(),
+        ZshSequence::HostnameShort => "\\h".to_string(),
+        // bash には省略なしのディレクトリ表示がないため $PWD を展開させる
+        ZshSequence::CurrentDirectoryFull => "${PWD}".to_string(),
+        ZshSequence::CurrentDirectoryTilde => "\\w".to_string(),
+        ZshSequence::PrivilegedIndicator => "\\$".to_string(),
+        ZshSequence::Newline => "\\n".to_string(),
+        ZshSequence::Literal(s) => escape_literal(s),
+        ZshSequence::Icon(icon, set) => escape_literal(icon.glyph(*set)),
+        ZshSequence::IconSpace(set) => set.space().to_string(),
+        // promptvars により $? が展開される
+        ZshSequence::ExitStatus => "$?".to_string(),
+        ZshSequence::Conditional { .. } => return None,
+    };
+    Some(s)
+}
+
+/// Renders the sequences as a bash `PS1` string.
+pub fn render(sequences: &[ZshSequence]) -> RenderOutput {
+    let mut output = RenderOutput::default();
+    for seq in sequences {
+        match render_sequence(seq) {
+            Some(s) => output.prompt.push_str(&s),
+            None => output.unsupported.push(seq.clone()),
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NamedColor;
+
+    #[test]
+    fn test_render_every_variant() {
+        let cases = vec![
+            (ZshSequence::Percent, "%"),
+            (ZshSequence::BoldStart, "\\[\\e[1m\\]"),
+            (ZshSequence::BoldEnd, "\\[\\e[22m\\]"),
+            (ZshSequence::UnderlineStart, "\\[\\e[4m\\]"),
+            (ZshSequence::UnderlineEnd, "\\[\\e[24m\\]"),
+            (ZshSequence::StandoutStart, "\\[\\e[7m\\]"),
+            (ZshSequence::StandoutEnd, "\\[\\e[27m\\]"),
+            (
+                ZshSequence::ForegroundColor(NamedColor::Red),
+                "\\[\\e[31m\\]",
+            ),
+            (ZshSequence::ForegroundColorEnd, "\\[\\e[39m\\]"),
+            (
+                ZshSequence::BackgroundColor(NamedColor::Code256(200)),
+                "\\[\\e[48;5;200m\\]",
+            ),
+            (ZshSequence::BackgroundColorEnd, "\\[\\e[49m\\]"),
+            (ZshSequence::ResetStyles, "\\[\\e[0m\\]"),
+            (ZshSequence::Username, "\\u"),
+            (ZshSequence::HostnameShort, "\\h"),
+            (ZshSequence::CurrentDirectoryFull, "${PWD}"),
+            (ZshSequence::CurrentDirectoryTilde, "\\w"),
+            (ZshSequence::PrivilegedIndicator, "\\$"),
+            (ZshSequence::Newline, "\\n"),
+            (ZshSequence::Literal("日本".to_string()), "日本"),
+            (ZshSequence::ExitStatus, "$?"),
+        ];
+        for (seq, expected) in cases {
+            assert_eq!(
+                render_sequence(&seq).as_deref(),
+                Some(expected),
+                "{:?}",
+                seq
+            );
+        }
+    }
+
+    #[test]
+    fn test_conditional_is_unsupported() {
+        let conditional = ZshSequence::Conditional {
+            condition: crate::sequences::ZshCondition::ExitStatus(0),
+            when_true: vec![],
+            when_false: vec![ZshSequence::ExitStatus],
+        };
+        let output = render(&[ZshSequence::Username, conditional.clone()]);
+        assert_eq!(output.prompt, "\\u");
+        assert_eq!(output.unsupported, vec![conditional]);
+    }
+
+    #[test]
+    fn test_literal_escaping() {
+        assert_eq!(
+            escape_literal("$HOME `id` \\ 100%!"),
+            "\\\\$HOME \\\\`id\\\\` \\\\\\\\ 100%!!"
+        );
+    }
+
+    #[test]
+    fn test_full_color_foreground() {
+        let output = render(&[
+            ZshSequence::ForegroundColor(NamedColor::FullColor((1, 2, 3))),
+            ZshSequence::Literal("x".to_string()),
+        ]);
+        assert_eq!(output.prompt, "\\[\\e[38;2;1;2;3m\\]x");
+        assert!(output.unsupported.is_empty());
+    }
+}
@@ -0,0 +1,285 @@
+//! Fish `fish_prompt` backend.
+
+use crate::backends::RenderOutput;
+use crate::colors::NamedColor;
+use crate::sequences::{ZshCondition, ZshSequence};
+
+/// Styles currently in effect, needed because fish can only reset all of them at once.
+#[derive(Clone, Default)]
+struct State {
+    fg: Option<NamedColor>,
+    bg: Option<NamedColor>,
+    bold: bool,
+    underline: bool,
+    reverse: bool,
+}
+
+impl State {
+    /// Commands that reset the colors and re-apply whatever is still active.
+    fn restore(&self) -> Vec<String> {
+        let mut lines = vec!["set_color normal".to_string()];
+        if let Some(fg) = self.fg {
+            lines.push(format!("set_color {}", color_name(&fg)));
+        }
+        if let Some(bg) = self.bg {
+            lines.push(format!("set_color -b {}", color_name(&bg)));
+        }
+        for (on, flag) in [
+            (self.bold, "--bold"),
+            (self.underline, "--underline"),
+            (self.reverse, "--reverse"),
+        ] {
+            if on {
+                lines.push(format!("set_color {}", flag));
+            }
+        }
+        lines
+    }
+}
+
+/// Maps a color to a `set_color` argument.
+///
+/// Colors without a fish name are passed as RGB hex, which fish downsamples if needed.
+fn color_name(color: &NamedColor) -> String {
+    match color {
+        NamedColor::Black => "black".to_string(),
+        NamedColor::Red => "red".to_string(),
+        NamedColor::Green => "green".to_string(),
+        NamedColor::Yellow => "yellow".to_string(),
+        NamedColor::Blue => "blue".to_string(),
+        NamedColor::Magenta => "magenta".to_string(),
+        NamedColor::Cyan => "cyan".to_string(),
+        NamedColor::White | NamedColor::LightWhite => "white".to_string(),
+        NamedColor::LightRed => "brred".to_string(),
+        NamedColor::LightGreen => "brgreen".to_string(),
+        NamedColor::LightYellow => "bryellow".to_string(),
+        NamedColor::LightBlue => "brblue".to_string(),
+        NamedColor::LightMagenta => "brmagenta".to_string(),
+        NamedColor::LightCyan => "brcyan".to_string(),
+        NamedColor::LightBlack | NamedColor::Code256(_) | NamedColor::FullColor(_) => {
+            let (r, g, b) = color.to_rgb();
+            format!("{:02x}{:02x}{:02x}", r, g, b)
+        }
+    }
+}
+
+/// Quotes text as a fish single-quoted string.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\\', "\\\\").replace('\'', "\\'"))
+}
+
+fn print(arg: &str) -> String {
+    format!("printf '%s' {}", arg)
+}
+
+fn render_sequence(seq: &ZshSequence, state: &mut State) -> Vec<String> {
+    let line = match seq {
+        ZshSequence::Percent => print("'%'"),
+        ZshSequence::BoldStart => {
+            state.bold = true;
+            "set_color --bold".to_string()
+        }
+        ZshSequence::UnderlineStart => {
+            state.underline = true;
+            "set_color --underline".to_string()
+        }
+        ZshSequence::StandoutStart => {
+            state.reverse = true;
+            "set_color --reverse".to_string()
+        }
+        ZshSequence::ForegroundColor(color) => {
+            state.fg = Some(*color);
+            format!("set_color {}", color_name(color))
+        }
+        ZshSequence::BackgroundColor(color) => {
+            state.bg = Some(*color);
+            format!("set_color -b {}", color_name(color))
+        }
+        ZshSequence::BoldEnd => {
+            state.bold = false;
+            return state.restore();
+        }
+        ZshSequence::UnderlineEnd => {
+            state.underline = false;
+            return state.restore();
+        }
+        ZshSequence::StandoutEnd => {
+            state.reverse = false;
+            return state.restore();
+        }
+        ZshSequence::ForegroundColorEnd => {
+            state.fg = None;
+            return state.restore();
+        }
+        ZshSequence::BackgroundColorEnd => {
+            state.bg = None;
+            return state.restore();
+        }
+        ZshSequence::ResetStyles => {
+            *state = State::default();
+            "set_color normal".to_string()
+        }
+        ZshSequence::Username => print("$USER"),
+        ZshSequence::HostnameShort => print("(prompt_hostname)"),
+        ZshSequence::CurrentDirectoryFull => print("$PWD"),
+        // %~ と同じく省略なしで表示する
+        ZshSequence::CurrentDirectoryTilde => print("(prompt_pwd --dir-length=0)"),
+        ZshSequence::PrivilegedIndicator => {
+            "fish_is_root_user; and printf '#'; or printf '%%'".to_string()
+        }
+        ZshSequence::Newline => "echo".to_string(),
+        ZshSequence::Literal(s) => print(&quote(s)),
+        ZshSequence::Icon(icon, set) => print(&quote(icon.glyph(*set))),
+        ZshSequence::IconSpace(set) => print(&quote(set.space())),
+        ZshSequence::ExitStatus => print("$last_status"),
+        ZshSequence::Conditional {
+            condition,
+            when_true,
+            when_false,
+        } => {
+            let test = match condition {
+                ZshCondition::ExitStatus(n) => format!("test $last_status -eq {}", n),
+                ZshCondition::Privileged => "fish_is_root_user".to_string(),
+            };
+            let mut lines = vec![format!("if {}", test)];
+            lines.extend(render_branch(when_true, &mut state.clone()));
+            if !when_false.is_empty() {
+                lines.push("else".to_string());
+                lines.extend(render_branch(when_false, &mut state.clone()));
+            }
+            lines.push("end".to_string());
+            return lines;
+        }
+    };
+    vec![line]
+}
+
+/// Renders the body of an `if` block, indented one level.
+fn render_branch(sequences: &[ZshSequence], state: &mut State) -> Vec<String> {
+    sequences
+        .iter()
+        .flat_map(|seq| render_sequence(seq, state))
+        .map(|line| format!("    {}", line))
+        .collect()
+}
+
+/// Renders the sequences as a complete `fish_prompt` function definition.
+pub fn render(sequences: &[ZshSequence]) -> RenderOutput {
+    let mut state = State::default();
+    // $status は最初のコマンドで上書きされるため先に保存する
+    let mut prompt = String::from("function fish_prompt\n    set -l last_status $status\n");
+    for seq in sequences {
+        for line in render_sequence(seq, &mut state) {
+            prompt.push_str("    ");
+            prompt.push_str(&line);
+            prompt.push('\n');
+        }
+    }
+    prompt.push_str("    set_color normal\nend\n");
+    RenderOutput {
+        prompt,
+        unsupported: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn snapshot(seq: ZshSequence) -> String {
+        render_sequence(&seq, &mut State::default()).join("\n")
+    }
+
+    #[test]
+    fn test_render_every_variant() {
+        let cases = vec![
+            (ZshSequence::Percent, "printf '%s' '%'"),
+            (ZshSequence::BoldStart, "set_color --bold"),
+            (ZshSequence::BoldEnd, "set_color normal"),
+            (ZshSequence::UnderlineStart, "set_color --underline"),
+            (ZshSequence::UnderlineEnd, "set_color normal"),
+            (ZshSequence::StandoutStart, "set_color --reverse"),
+            (ZshSequence::StandoutEnd, "set_color normal"),
+            (
+                ZshSequence::ForegroundColor(NamedColor::LightRed),
+                "set_color brred",
+            ),
+            (ZshSequence::ForegroundColorEnd, "set_color normal"),
+            (
+                ZshSequence::BackgroundColor(NamedColor::Code256(196)),
+                "set_color -b ff0000",
+            ),
+            (ZshSequence::BackgroundColorEnd, "set_color normal"),
+            (ZshSequence::ResetStyles, "set_color normal"),
+            (ZshSequence::Username, "printf '%s' $USER"),
+            (ZshSequence::HostnameShort, "printf '%s' (prompt_hostname)"),
+            (ZshSequence::CurrentDirectoryFull, "printf '%s' $PWD"),
+            (
+                ZshSequence::CurrentDirectoryTilde,
+                "printf '%s' (prompt_pwd --dir-length=0)",
+            ),
+            (
+                ZshSequence::PrivilegedIndicator,
+                "fish_is_root_user; and printf '#'; or printf '%%'",
+            ),
+            (ZshSequence::Newline, "echo"),
+            (
+                ZshSequence::Literal("it's -n \\".to_string()),
+                "printf '%s' 'it\\'s -n \\\\'",
+            ),
+            (ZshSequence::ExitStatus, "printf '%s' $last_status"),
+            (
+                ZshSequence::Conditional {
+                    condition: ZshCondition::ExitStatus(0),
+                    when_true: vec![],
+                    when_false: vec![
+                        ZshSequence::ForegroundColor(NamedColor::Red),
+                        ZshSequence::ExitStatus,
+                    ],
+                },
+                "if test $last_status -eq 0\n\
+                 else\n\
+                 \x20   set_color red\n\
+                 \x20   printf '%s' $last_status\n\
+                 end",
+            ),
+            (
+                ZshSequence::Conditional {
+                    condition: ZshCondition::Privileged,
+                    when_true: vec![ZshSequence::Literal("#".to_string())],
+                    when_false: vec![],
+                },
+                "if fish_is_root_user\n\
+                 \x20   printf '%s' '#'\n\
+                 end",
+            ),
+        ];
+        for (seq, expected) in cases {
+            assert_eq!(snapshot(seq.clone()), expected, "{:?}", seq);
+        }
+    }
+
+    #[test]
+    fn test_ending_one_style_restores_the_others() {
+        let output = render(&[
+            ZshSequence::BoldStart,
+            ZshSequence::ForegroundColor(NamedColor::Green),
+            ZshSequence::Username,
+            ZshSequence::BoldEnd,
+            ZshSequence::Literal("$".to_string()),
+        ]);
+        assert_eq!(
+            output.prompt,
+            "function fish_prompt\n\
+             \x20   set -l last_status $status\n\
+             \x20   set_color --bold\n\
+             \x20   set_color green\n\
+             \x20   printf '%s' $USER\n\
+             \x20   set_color normal\n\
+             \x20   set_color green\n\
+             \x20   printf '%s' '$'\n\
+             \x20   set_color normal\n\
+             end\n"
+        );
+    }
+}
@@ -0,0 +1,87 @@
+//! HTML export of a rendered prompt, for documentation.
+
+use crate::backends::ExportOptions;
+use crate::backends::styled::{self, Style, escape_xml, hex};
+use crate::context::PromptContext;
+use crate::sequences::ZshSequence;
+
+fn css(style: &Style, options: &ExportOptions) -> String {
+    let mut rules = Vec::new();
+    if style.fg.is_some() || style.standout {
+        let (fg, _) = style.effective_colors(options.foreground, options.background);
+        rules.push(format!("color:{}", hex(&fg)));
+    }
+    if style.bg.is_some() || style.standout {
+        let (_, bg) = style.effective_colors(options.foreground, options.background);
+        rules.push(format!("background-color:{}", hex(&bg)));
+    }
+    if style.bold {
+        rules.push("font-weight:bold".to_string());
+    }
+    if style.underline {
+        rules.push("text-decoration:underline".to_string());
+    }
+    rules.join(";")
+}
+
+/// Renders the prompt as a `<pre>` block of `<span style>` runs.
+pub fn render(sequences: &[ZshSequence], ctx: &PromptContext, options: &ExportOptions) -> String {
+    let mut html = format!(
+        "<pre style=\"background-color:{};color:{};font-family:monospace\">",
+        hex(&options.background),
+        hex(&options.foreground)
+    );
+    for (i, line) in styled::styled_lines(sequences, ctx).iter().enumerate() {
+        if i > 0 {
+            html.push('\n');
+        }
+        for (style, text) in line {
+            let rules = css(style, options);
+            if rules.is_empty() {
+                html.push_str(&escape_xml(text));
+            } else {
+                html.push_str(&format!(
+                    "<span style=\"{}\">{}</span>",
+                    rules,
+                    escape_xml(text)
+                ));
+            }
+        }
+    }
+    html.push_str("</pre>");
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NamedColor;
+
+    #[test]
+    fn test_render_html() {
+        let ctx = PromptContext {
+            username: "alice".to_string(),
+            ..Default::default()
+        };
+        let html = render(
+            &[
+                ZshSequence::BoldStart,
+                ZshSequence::ForegroundColor(NamedColor::Red),
+                ZshSequence::Username,
+                ZshSequence::ResetStyles,
+                ZshSequence::Literal(" <&> ".to_string()),
+                ZshSequence::StandoutStart,
+                ZshSequence::UnderlineStart,
+                ZshSequence::Literal("x".to_string()),
+            ],
+            &ctx,
+            &ExportOptions::default(),
+        );
+        assert_eq!(
+            html,
+            "<pre style=\"background-color:#000000;color:#e5e5e5;font-family:monospace\">\
+             <span style=\"color:#cd0000;font-weight:bold\">alice</span> &lt;&amp;&gt; \
+             <span style=\"color:#000000;background-color:#e5e5e5;text-decoration:underline\">x</span></pre>"
+        );
+    }
+}
@@ -0,0 +1,70 @@
+//! Renderers that translate `ZshSequence`s into prompt syntax for other shells,
+//! plain ANSI text and tmux format strings, and exporters for HTML and SVG.
+
+pub mod ansi;
+pub mod bash;
+pub mod fish;
+pub mod html;
+pub mod powershell;
+pub mod styled;
+pub mod svg;
+pub mod tmux;
+
+use crate::colors::NamedColor;
+use crate::sequences::ZshSequence;
+
+/// Target shell for `ZshPromptBuilder::build_for`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Shell {
+    Zsh,
+    Bash,
+    /// Produces a complete `fish_prompt` function.
+    Fish,
+    /// Produces a complete `function prompt {}` definition.
+    PowerShell,
+}
+
+/// Result of rendering a prompt for a specific shell.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct RenderOutput {
+    /// The prompt string in the target shell's syntax.
+    pub prompt: String,
+    /// Sequences that have no equivalent in the target shell and were dropped.
+    pub unsupported: Vec<ZshSequence>,
+}
+
+/// Settings for the HTML and SVG exporters.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ExportOptions {
+    /// Terminal default foreground, used where the prompt sets no color.
+    pub foreground: NamedColor,
+    /// Terminal default background.
+    pub background: NamedColor,
+    /// Font size in pixels (SVG only).
+    pub font_size: f64,
+}
+
+impl Default for ExportOptions {
+    fn default() -> Self {
+        Self {
+            foreground: NamedColor::White,
+            background: NamedColor::Black,
+            font_size: 14.0,
+        }
+    }
+}
+
+impl Shell {
+    /// Renders the sequences into this shell's prompt syntax.
+    pub fn render(&self, sequences: &[ZshSequence]) -> RenderOutput {
+        match self {
+            Shell::Zsh => RenderOutput {
+                prompt: sequences.iter().map(|seq| seq.to_string()).collect(),
+                unsupported: Vec::new(),
+            },
+            Shell::Bash => bash::render(sequences),
+            Shell::Fish => fish::render(sequences),
+            Shell::PowerShell => powershell::render(sequences),
+        }
+    }
+}
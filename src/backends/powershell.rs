@@ -0,0 +1,171 @@
+//! PowerShell `prompt` function backend.
+
+use crate::backends::RenderOutput;
+use crate::sequences::{ZshCondition, ZshSequence};
+
+/// An ANSI escape as a PowerShell expandable string, using the `$e` defined by `render`.
+fn sgr(params: &str) -> String {
+    format!("\"$e[{}m\"", params)
+}
+
+/// Quotes text as a PowerShell verbatim string.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', "''"))
+}
+
+/// Converts a single sequence into a PowerShell expression.
+fn render_sequence(seq: &ZshSequence) -> String {
+    match seq {
+        ZshSequence::Percent => "'%'".to_string(),
+        ZshSequence::BoldStart => sgr("1"),
+        ZshSequence::BoldEnd => sgr("22"),
+        ZshSequence::UnderlineStart => sgr("4"),
+        ZshSequence::UnderlineEnd => sgr("24"),
+        ZshSequence::StandoutStart => sgr("7"),
+        ZshSequence::StandoutEnd => sgr("27"),
+        ZshSequence::ForegroundColor(color) => sgr(&color.to_ansi_sgr(false)),
+        ZshSequence::ForegroundColorEnd => sgr("39"),
+        ZshSequence::BackgroundColor(color) => sgr(&color.to_ansi_sgr(true)),
+        ZshSequence::BackgroundColorEnd => sgr("49"),
+        ZshSequence::ResetStyles => sgr("0"),
+        ZshSequence::Username => "[Environment]::UserName".to_string(),
+        ZshSequence::HostnameShort => "[Environment]::MachineName.Split('.')[0]".to_string(),
+        ZshSequence::CurrentDirectoryFull => "$PWD.Path".to_string(),
+        // パスの区切りまで一致したときだけ置き換え、大文字小文字も区別する
+        ZshSequence::CurrentDirectoryTilde => {
+            r"($PWD.Path -creplace ('^' + [regex]::Escape($HOME) + '($|[\\/])'), '~$1')".to_string()
+        }
+        ZshSequence::PrivilegedIndicator => "$(if ($root) { '#' } else { '%' })".to_string(),
+        ZshSequence::Newline => "\"`n\"".to_string(),
+        ZshSequence::Literal(s) => quote(s),
+        ZshSequence::Icon(icon, set) => quote(icon.glyph(*set)),
+        ZshSequence::IconSpace(set) => quote(set.space()),
+        ZshSequence::ExitStatus => "$code".to_string(),
+        ZshSequence::Conditional {
+            condition,
+            when_true,
+            when_false,
+        } => {
+            let test = match condition {
+                ZshCondition::ExitStatus(n) => format!("$code -eq {}", n),
+                ZshCondition::Privileged => "$root".to_string(),
+            };
+            format!(
+                "$(if ({}) {{ {} }} else {{ {} }})",
+                test,
+                render_branch(when_true),
+                render_branch(when_false)
+            )
+        }
+    }
+}
+
+fn render_branch(sequences: &[ZshSequence]) -> String {
+    let parts: Vec<String> = sequences.iter().map(render_sequence).collect();
+    format!("-join @({})", parts.join(", "))
+}
+
+/// Renders the sequences as a complete `function prompt {}` definition.
+pub fn render(sequences: &[ZshSequence]) -> RenderOutput {
+    // $? は次の文で上書きされるため最初に終了コードを確定させる。
+    // Windows PowerShell には $IsWindows も id コマンドもないので PSEdition で判定する
+    let mut prompt = String::from(
+        "function prompt {\n    $code = if ($?) { 0 } elseif ($global:LASTEXITCODE) { $global:LASTEXITCODE } else { 1 }\n    $e = [char]27\n    $root = if ($IsWindows -or $PSVersionTable.PSEdition -eq 'Desktop') { ([Security.Principal.WindowsPrincipal][Security.Principal.WindowsIdentity]::GetCurrent()).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator) } else { (id -u 2>$null) -eq 0 }\n    $p = ''\n",
+    );
+    for seq in sequences {
+        prompt.push_str("    $p += ");
+        prompt.push_str(&render_sequence(seq));
+        prompt.push('\n');
+    }
+    prompt.push_str("    $p\n}\n");
+    RenderOutput {
+        prompt,
+        unsupported: Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NamedColor;
+
+    #[test]
+    fn test_render_every_variant() {
+        let cases = vec![
+            (ZshSequence::Percent, "'%'"),
+            (ZshSequence::BoldStart, "\"$e[1m\""),
+            (ZshSequence::BoldEnd, "\"$e[22m\""),
+            (ZshSequence::UnderlineStart, "\"$e[4m\""),
+            (ZshSequence::UnderlineEnd, "\"$e[24m\""),
+            (ZshSequence::StandoutStart, "\"$e[7m\""),
+            (ZshSequence::StandoutEnd, "\"$e[27m\""),
+            (
+                ZshSequence::ForegroundColor(NamedColor::FullColor((10, 20, 30))),
+                "\"$e[38;2;10;20;30m\"",
+            ),
+            (ZshSequence::ForegroundColorEnd, "\"$e[39m\""),
+            (ZshSequence::BackgroundColor(NamedColor::Blue), "\"$e[44m\""),
+            (ZshSequence::BackgroundColorEnd, "\"$e[49m\""),
+            (ZshSequence::ResetStyles, "\"$e[0m\""),
+            (ZshSequence::Username, "[Environment]::UserName"),
+            (
+                ZshSequence::HostnameShort,
+                "[Environment]::MachineName.Split('.')[0]",
+            ),
+            (ZshSequence::CurrentDirectoryFull, "$PWD.Path"),
+            (
+                ZshSequence::CurrentDirectoryTilde,
+                r"($PWD.Path -creplace ('^' + [regex]::Escape($HOME) + '($|[\\/])'), '~$1')",
+            ),
+            (
+                ZshSequence::PrivilegedIndicator,
+                "$(if ($root) { '#' } else { '%' })",
+            ),
+            (ZshSequence::Newline, "\"`n\""),
+            (ZshSequence::Literal("it's $x".to_string()), "'it''s $x'"),
+            (ZshSequence::ExitStatus, "$code"),
+            (
+                ZshSequence::Conditional {
+                    condition: ZshCondition::ExitStatus(0),
+                    when_true: vec![],
+                    when_false: vec![
+                        ZshSequence::ForegroundColor(NamedColor::Red),
+                        ZshSequence::ExitStatus,
+                    ],
+                },
+                "$(if ($code -eq 0) { -join @() } else { -join @(\"$e[31m\", $code) })",
+            ),
+            (
+                ZshSequence::Conditional {
+                    condition: ZshCondition::Privileged,
+                    when_true: vec![ZshSequence::Literal("#".to_string())],
+                    when_false: vec![],
+                },
+                "$(if ($root) { -join @('#') } else { -join @() })",
+            ),
+        ];
+        for (seq, expected) in cases {
+            assert_eq!(render_sequence(&seq), expected, "{:?}", seq);
+        }
+    }
+
+    #[test]
+    fn test_render_function() {
+        let output = render(&[
+            ZshSequence::Username,
+            ZshSequence::Literal("> ".to_string()),
+        ]);
+        assert_eq!(
+            output.prompt,
+            "function prompt {\n\
+             \x20   $code = if ($?) { 0 } elseif ($global:LASTEXITCODE) { $global:LASTEXITCODE } else { 1 }\n\
+             \x20   $e = [char]27\n\
+             \x20   $root = if ($IsWindows -or $PSVersionTable.PSEdition -eq 'Desktop') { ([Security.Principal.WindowsPrincipal][Security.Principal.WindowsIdentity]::GetCurrent()).IsInRole([Security.Principal.WindowsBuiltInRole]::Administrator) } else { (id -u 2>$null) -eq 0 }\n\
+             \x20   $p = ''\n\
+             \x20   $p += [Environment]::UserName\n\
+             \x20   $p += '> '\n\
+             \x20   $p\n\
+             }\n"
+        );
+    }
+}
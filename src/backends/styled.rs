@@ -0,0 +1,127 @@
+//! Resolves a sequence list into runs of text that share the same style,
+//! for exporters that need explicit style state (HTML, SVG).
+
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::sequences::{ZshSequence, expand_conditionals};
+
+/// The style in effect for a run of text.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<NamedColor>,
+    pub bg: Option<NamedColor>,
+    pub bold: bool,
+    pub underline: bool,
+    pub standout: bool,
+}
+
+impl Style {
+    /// Applies a style sequence. Returns false if `seq` is not a style sequence.
+    pub fn apply(&mut self, seq: &ZshSequence) -> bool {
+        match seq {
+            ZshSequence::BoldStart => self.bold = true,
+            ZshSequence::BoldEnd => self.bold = false,
+            ZshSequence::UnderlineStart => self.underline = true,
+            ZshSequence::UnderlineEnd => self.underline = false,
+            ZshSequence::StandoutStart => self.standout = true,
+            ZshSequence::StandoutEnd => self.standout = false,
+            ZshSequence::ForegroundColor(color) => self.fg = Some(*color),
+            ZshSequence::ForegroundColorEnd => self.fg = None,
+            ZshSequence::BackgroundColor(color) => self.bg = Some(*color),
+            ZshSequence::BackgroundColorEnd => self.bg = None,
+            ZshSequence::ResetStyles => *self = Style::default(),
+            _ => return false,
+        }
+        true
+    }
+
+    /// Foreground and background after applying standout, filling in the defaults.
+    pub fn effective_colors(
+        &self,
+        default_fg: NamedColor,
+        default_bg: NamedColor,
+    ) -> (NamedColor, NamedColor) {
+        let fg = self.fg.unwrap_or(default_fg);
+        let bg = self.bg.unwrap_or(default_bg);
+        if self.standout { (bg, fg) } else { (fg, bg) }
+    }
+}
+
+/// Splits the prompt into lines of styled runs. Empty runs are dropped.
+pub fn styled_lines(sequences: &[ZshSequence], ctx: &PromptContext) -> Vec<Vec<(Style, String)>> {
+    let mut lines: Vec<Vec<(Style, String)>> = vec![Vec::new()];
+    let mut style = Style::default();
+    for seq in &expand_conditionals(sequences, ctx) {
+        if style.apply(seq) {
+            continue;
+        }
+        let text = seq.resolve(ctx);
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Vec::new());
+            }
+            if part.is_empty() {
+                continue;
+            }
+            let line = lines.last_mut().expect("lines is never empty");
+            match line.last_mut() {
+                Some((last, s)) if *last == style => s.push_str(part),
+                _ => line.push((style, part.to_string())),
+            }
+        }
+    }
+    lines
+}
+
+/// Formats a color as `#rrggbb`.
+pub fn hex(color: &NamedColor) -> String {
+    let (r, g, b) = color.to_rgb();
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+/// Escapes text for HTML/XML content and attribute values.
+pub fn escape_xml(s: &str) -> String {
+    let mut result = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '&' => result.push_str("&amp;"),
+            '<' => result.push_str("&lt;"),
+            '>' => result.push_str("&gt;"),
+            '"' => result.push_str("&quot;"),
+            '\'' => result.push_str("&#39;"),
+            _ => result.push(c),
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_styled_lines_merges_and_splits() {
+        let lines = styled_lines(
+            &[
+                ZshSequence::BoldStart,
+                ZshSequence::Literal("a".to_string()),
+                ZshSequence::Literal("b".to_string()),
+                ZshSequence::Newline,
+                ZshSequence::BoldEnd,
+                ZshSequence::Literal("c".to_string()),
+            ],
+            &PromptContext::default(),
+        );
+        let bold = Style {
+            bold: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            lines,
+            vec![
+                vec![(bold, "ab".to_string())],
+                vec![(Style::default(), "c".to_string())]
+            ]
+        );
+    }
+}
@@ -0,0 +1,117 @@
+//! Standalone SVG export of a rendered prompt, laid out on a monospace grid.
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::backends::ExportOptions;
+use crate::backends::styled::{self, escape_xml, hex};
+use crate::context::PromptContext;
+use crate::sequences::ZshSequence;
+
+/// Width of one terminal cell relative to the font size.
+const CELL_WIDTH_RATIO: f64 = 0.6;
+/// Height of one line relative to the font size.
+const LINE_HEIGHT_RATIO: f64 = 1.4;
+/// Space around the text, in cells.
+const PADDING: f64 = 1.0;
+
+/// Renders the prompt as an SVG document.
+///
+/// Every run is positioned by its column and stretched with `textLength` to
+/// its display width, so wide characters occupy two cells as in a terminal.
+pub fn render(sequences: &[ZshSequence], ctx: &PromptContext, options: &ExportOptions) -> String {
+    let lines = styled::styled_lines(sequences, ctx);
+    let cell_width = options.font_size * CELL_WIDTH_RATIO;
+    let line_height = options.font_size * LINE_HEIGHT_RATIO;
+    let columns = lines
+        .iter()
+        .map(|line| line.iter().map(|(_, text)| text.width()).sum::<usize>())
+        .max()
+        .unwrap_or(0);
+    let width = (columns as f64 + PADDING * 2.0) * cell_width;
+    let height = lines.len() as f64 * line_height + PADDING * 2.0 * cell_width;
+
+    let mut body = String::new();
+    for (row, line) in lines.iter().enumerate() {
+        let top = PADDING * cell_width + row as f64 * line_height;
+        let baseline = top + options.font_size;
+        let mut column = 0;
+        for (style, text) in line {
+            let x = (PADDING + column as f64) * cell_width;
+            let run_width = text.width() as f64 * cell_width;
+            let (fg, bg) = style.effective_colors(options.foreground, options.background);
+            if style.bg.is_some() || style.standout {
+                body.push_str(&format!(
+                    "<rect x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\" fill=\"{}\"/>\n",
+                    x,
+                    top,
+                    run_width,
+                    line_height,
+                    hex(&bg)
+                ));
+            }
+            let mut attrs = format!(
+                "x=\"{:.1}\" y=\"{:.1}\" textLength=\"{:.1}\" lengthAdjust=\"spacingAndGlyphs\" fill=\"{}\"",
+                x,
+                baseline,
+                run_width,
+                hex(&fg)
+            );
+            if style.bold {
+                attrs.push_str(" font-weight=\"bold\"");
+            }
+            if style.underline {
+                attrs.push_str(" text-decoration=\"underline\"");
+            }
+            body.push_str(&format!("<text {}>{}</text>\n", attrs, escape_xml(text)));
+            column += text.width();
+        }
+    }
+
+    format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w:.1}\" height=\"{h:.1}\" viewBox=\"0 0 {w:.1} {h:.1}\" \
+         font-family=\"monospace\" font-size=\"{fs}\" xml:space=\"preserve\">\n\
+         <rect width=\"100%\" height=\"100%\" fill=\"{bg}\"/>\n{body}</svg>\n",
+        w = width,
+        h = height,
+        fs = options.font_size,
+        bg = hex(&options.background),
+        body = body
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::colors::NamedColor;
+
+    #[test]
+    fn test_render_svg_layout() {
+        let svg = render(
+            &[
+                ZshSequence::Literal("日本".to_string()),
+                ZshSequence::BackgroundColor(NamedColor::Blue),
+                ZshSequence::BoldStart,
+                ZshSequence::Literal("ab".to_string()),
+                ZshSequence::Newline,
+                ZshSequence::Literal(">".to_string()),
+            ],
+            &PromptContext::default(),
+            &ExportOptions {
+                font_size: 10.0,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            svg,
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"48.0\" height=\"40.0\" viewBox=\"0 0 48.0 40.0\" \
+             font-family=\"monospace\" font-size=\"10\" xml:space=\"preserve\">\n\
+             <rect width=\"100%\" height=\"100%\" fill=\"#000000\"/>\n\
+             <text x=\"6.0\" y=\"16.0\" textLength=\"24.0\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#e5e5e5\">日本</text>\n\
+             <rect x=\"30.0\" y=\"6.0\" width=\"12.0\" height=\"14.0\" fill=\"#0000ee\"/>\n\
+             <text x=\"30.0\" y=\"16.0\" textLength=\"12.0\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#e5e5e5\" font-weight=\"bold\">ab</text>\n\
+             <rect x=\"6.0\" y=\"20.0\" width=\"6.0\" height=\"14.0\" fill=\"#0000ee\"/>\n\
+             <text x=\"6.0\" y=\"30.0\" textLength=\"6.0\" lengthAdjust=\"spacingAndGlyphs\" fill=\"#e5e5e5\" font-weight=\"bold\">&gt;</text>\n\
+             </svg>\n"
+        );
+    }
+}
@@ -0,0 +1,105 @@
+//! tmux format string backend (`#[fg=red,bold]`), for status lines.
+
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::sequences::{ZshSequence, expand_conditionals};
+
+/// Maps a color to a tmux color name.
+fn color_name(color: &NamedColor) -> String {
+    match color {
+        NamedColor::Black => "black".to_string(),
+        NamedColor::Red => "red".to_string(),
+        NamedColor::Green => "green".to_string(),
+        NamedColor::Yellow => "yellow".to_string(),
+        NamedColor::Blue => "blue".to_string(),
+        NamedColor::Magenta => "magenta".to_string(),
+        NamedColor::Cyan => "cyan".to_string(),
+        NamedColor::White | NamedColor::LightWhite => "white".to_string(),
+        NamedColor::LightBlack => "colour240".to_string(),
+        NamedColor::LightRed => "brightred".to_string(),
+        NamedColor::LightGreen => "brightgreen".to_string(),
+        NamedColor::LightYellow => "brightyellow".to_string(),
+        NamedColor::LightBlue => "brightblue".to_string(),
+        NamedColor::LightMagenta => "brightmagenta".to_string(),
+        NamedColor::LightCyan => "brightcyan".to_string(),
+        NamedColor::Code256(code) => format!("colour{}", code),
+        NamedColor::FullColor((r, g, b)) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+    }
+}
+
+/// Returns the style attribute for a style sequence, or `None` for text.
+fn style_attribute(seq: &ZshSequence) -> Option<String> {
+    let attr = match seq {
+        ZshSequence::BoldStart => "bold".to_string(),
+        ZshSequence::BoldEnd => "nobold".to_string(),
+        ZshSequence::UnderlineStart => "underscore".to_string(),
+        ZshSequence::UnderlineEnd => "nounderscore".to_string(),
+        ZshSequence::StandoutStart => "reverse".to_string(),
+        ZshSequence::StandoutEnd => "noreverse".to_string(),
+        ZshSequence::ForegroundColor(color) => format!("fg={}", color_name(color)),
+        ZshSequence::ForegroundColorEnd => "fg=default".to_string(),
+        ZshSequence::BackgroundColor(color) => format!("bg={}", color_name(color)),
+        ZshSequence::BackgroundColorEnd => "bg=default".to_string(),
+        ZshSequence::ResetStyles => "default".to_string(),
+        _ => return None,
+    };
+    Some(attr)
+}
+
+/// Renders the sequences as a tmux format string.
+///
+/// Consecutive style sequences are merged into a single `#[...]` block and
+/// dynamic values are resolved from `ctx`. A literal `#` is written as `##`.
+pub fn render(sequences: &[ZshSequence], ctx: &PromptContext) -> String {
+    let mut output = String::new();
+    let mut pending: Vec<String> = Vec::new();
+    for seq in &expand_conditionals(sequences, ctx) {
+        if let Some(attr) = style_attribute(seq) {
+            pending.push(attr);
+            continue;
+        }
+        if !pending.is_empty() {
+            output.push_str(&format!("#[{}]", pending.join(",")));
+            pending.clear();
+        }
+        output.push_str(&seq.resolve(ctx).replace('#', "##"));
+    }
+    if !pending.is_empty() {
+        output.push_str(&format!("#[{}]", pending.join(",")));
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_tmux() {
+        let ctx = PromptContext {
+            hostname: "box".to_string(),
+            ..Default::default()
+        };
+        let output = render(
+            &[
+                ZshSequence::ForegroundColor(NamedColor::Red),
+                ZshSequence::BoldStart,
+                ZshSequence::HostnameShort,
+                ZshSequence::BoldEnd,
+                ZshSequence::BackgroundColor(NamedColor::Code256(236)),
+                ZshSequence::Literal(" #1".to_string()),
+                ZshSequence::ResetStyles,
+            ],
+            &ctx,
+        );
+        assert_eq!(
+            output,
+            "#[fg=red,bold]box#[nobold,bg=colour236] ##1#[default]"
+        );
+    }
+
+    #[test]
+    fn test_full_color() {
+        assert_eq!(color_name(&NamedColor::FullColor((255, 136, 0))), "#ff8800");
+    }
+}
@@ -1,12 +1,21 @@
 use regex::Regex;
 use unicode_width::UnicodeWidthStr;
 
+use crate::accessibility::{self, ColorBlindness};
+use crate::backends::{self, ExportOptions, RenderOutput, Shell};
 use crate::colors::NamedColor;
-use crate::sequences::ZshSequence;
+use crate::context::PromptContext;
+use crate::icons::{Icon, IconSet};
+use crate::markup::{self, MarkupError};
+use crate::options::BuildOptions;
+use crate::sequences::{ZshCondition, ZshSequence};
+use crate::validation::{self, ValidationIssue, ValidationOptions};
 
 /// A helper struct to build a prompt string
+#[derive(Debug, Clone)]
 pub struct ZshPromptBuilder {
     sequences: Vec<ZshSequence>,
+    icon_set: IconSet,
 }
 
 impl Default for ZshPromptBuilder {
@@ -19,9 +28,17 @@ impl ZshPromptBuilder {
     pub fn new() -> Self {
         Self {
             sequences: Vec::new(),
+            icon_set: IconSet::default(),
         }
     }
 
+    /// Builds a prompt from markup such as `<b><fg=red>%n</fg></b> {cwd}`.
+    ///
+    /// See the `markup` module for the syntax.
+    pub fn markup(markup: &str) -> Result<Self, MarkupError> {
+        Ok(Self::new().chain(markup::parse(markup)?))
+    }
+
     pub fn seq(mut self, sequence: ZshSequence) -> Self {
         self.sequences.push(sequence);
         self
@@ -32,6 +49,33 @@ impl ZshPromptBuilder {
         self
     }
 
+    /// Appends an icon drawn from the builder's icon set, followed by a space.
+    ///
+    /// Both are left out with `IconSet::None`, so the text after the icon
+    /// does not start with a stray space.
+    pub fn icon(self, icon: Icon) -> Self {
+        self.icon_glyph(icon).icon_space()
+    }
+
+    /// Appends an icon without the trailing space.
+    pub fn icon_glyph(mut self, icon: Icon) -> Self {
+        self.sequences.push(ZshSequence::Icon(icon, self.icon_set));
+        self
+    }
+
+    /// Appends a space that is only drawn when icons are.
+    pub fn icon_space(mut self) -> Self {
+        self.sequences.push(ZshSequence::IconSpace(self.icon_set));
+        self
+    }
+
+    /// Selects the icon set, including for icons that were already added.
+    pub fn with_icon_set(mut self, set: IconSet) -> Self {
+        self.icon_set = set;
+        set_sequence_icon_set(&mut self.sequences, set);
+        self
+    }
+
     pub fn color(mut self, color: NamedColor) -> Self {
         self.sequences.push(ZshSequence::ForegroundColor(color));
         self
@@ -42,6 +86,12 @@ impl ZshPromptBuilder {
         self
     }
 
+    /// Sets the background and picks black or white text for it.
+    pub fn color_bg_auto_fg(self, color: NamedColor) -> Self {
+        self.color_bg(color)
+            .color(NamedColor::auto_contrast_fg(color))
+    }
+
     pub fn reset_styles(mut self) -> Self {
         self.sequences.push(ZshSequence::ResetStyles);
         self
@@ -111,10 +161,35 @@ impl ZshPromptBuilder {
         self.sequences.push(ZshSequence::PrivilegedIndicator);
         self
     }
+    pub fn exit_status(mut self) -> Self {
+        self.sequences.push(ZshSequence::ExitStatus);
+        self
+    }
+
+    /// Shows `when_true` or `when_false` depending on `condition`, evaluated by zsh.
+    pub fn conditional(
+        mut self,
+        condition: ZshCondition,
+        when_true: ZshPromptBuilder,
+        when_false: ZshPromptBuilder,
+    ) -> Self {
+        self.sequences.push(ZshSequence::Conditional {
+            condition,
+            when_true: when_true.sequences,
+            when_false: when_false.sequences,
+        });
+        self
+    }
+
     pub fn newline(mut self) -> Self {
         self.sequences.push(ZshSequence::Newline);
         self
     }
+    /// The sequences added so far.
+    pub fn sequences(&self) -> &[ZshSequence] {
+        &self.sequences
+    }
+
     pub fn chain(mut self, list: Vec<ZshSequence>) -> Self {
         self.sequences.extend(list);
         self
@@ -123,6 +198,22 @@ impl ZshPromptBuilder {
         self.sequences.extend(other.sequences);
         self
     }
+
+    /// Appends the given parts with `separator` between them, skipping parts without sequences.
+    pub fn join(mut self, parts: impl IntoIterator<Item = Self>, separator: &Self) -> Self {
+        let mut first = true;
+        for part in parts {
+            if part.sequences.is_empty() {
+                continue;
+            }
+            if !first {
+                self.sequences.extend(separator.sequences.iter().cloned());
+            }
+            self.sequences.extend(part.sequences);
+            first = false;
+        }
+        self
+    }
     pub fn build(&self) -> String {
         self.sequences
             .iter()
@@ -130,19 +221,72 @@ impl ZshPromptBuilder {
             .collect::<String>()
     }
 
-    /// Extracts all literal text segments from the prompt builder and concatenates them.
+    /// Builds the prompt in the syntax of the given shell.
+    ///
+    /// Sequences without an equivalent in that shell are dropped;
+    /// use `render_for` to find out which ones.
+    pub fn build_for(&self, shell: Shell) -> String {
+        self.render_for(shell).prompt
+    }
+
+    /// Renders the prompt for the given shell, reporting unsupported sequences.
+    pub fn render_for(&self, shell: Shell) -> RenderOutput {
+        shell.render(&self.sequences)
+    }
+
+    /// Builds the prompt as plain text with ANSI escapes, resolving dynamic
+    /// sequences from `ctx`. Useful outside of zsh, e.g. in TUI output.
+    pub fn build_ansi(&self, ctx: &PromptContext) -> String {
+        backends::ansi::render(&self.sequences, ctx)
+    }
+
+    /// Builds the prompt as a tmux format string for status lines.
+    pub fn build_tmux(&self, ctx: &PromptContext) -> String {
+        backends::tmux::render(&self.sequences, ctx)
+    }
+
+    /// Exports the prompt as an HTML `<pre>` block, resolving dynamic sequences from `ctx`.
+    pub fn export_html(&self, ctx: &PromptContext, options: &ExportOptions) -> String {
+        backends::html::render(&self.sequences, ctx, options)
+    }
+
+    /// Exports the prompt as a standalone SVG image, resolving dynamic sequences from `ctx`.
+    pub fn export_svg(&self, ctx: &PromptContext, options: &ExportOptions) -> String {
+        backends::svg::render(&self.sequences, ctx, options)
+    }
+
+    /// Builds the prompt, leaving out the sequence kinds disabled in `options`.
+    pub fn build_with(&self, options: &BuildOptions) -> String {
+        options
+            .filter(&self.sequences)
+            .iter()
+            .map(|seq| seq.to_string())
+            .collect::<String>()
+    }
+
+    /// Removes all color sequences, optionally keeping bold/underline/standout.
+    pub fn strip_colors(mut self, keep_attributes: bool) -> Self {
+        let options = BuildOptions {
+            colors: false,
+            attributes: keep_attributes,
+        };
+        self.sequences = options.filter(&self.sequences);
+        self
+    }
+
+    /// Extracts the static text of the prompt and concatenates it.
     ///
-    /// This method collects all `ZshSequence::Literal` contents into a single String,
-    /// ignoring all other Zsh escape sequences (style, color, dynamic info).
+    /// This method collects the `ZshSequence::Literal` contents and the glyphs and
+    /// spacers of icons into a single String, ignoring all other Zsh escape
+    /// sequences (style, color, dynamic info).
     pub fn text(&self) -> String {
         self.sequences
             .iter()
-            .filter_map(|seq| {
-                if let ZshSequence::Literal(s) = seq {
-                    Some(s.clone())
-                } else {
-                    None
-                }
+            .filter_map(|seq| match seq {
+                ZshSequence::Literal(s) => Some(s.clone()),
+                ZshSequence::Icon(icon, set) => Some(icon.glyph(*set).to_string()),
+                ZshSequence::IconSpace(set) => Some(set.space().to_string()),
+                _ => None,
             })
             .collect::<String>()
     }
@@ -157,25 +301,79 @@ impl ZshPromptBuilder {
         //     Ok(out) => String::from_utf8_lossy(&out.stdout).trim_end().to_string(),
         //     Err(_) => self.text(),
         // }
-        self.sequences
-            .iter()
-            .map(|seg| {
-                let s = seg.raw_text();
-                eprintln!("{}", s);
-                s
-            })
-            .collect::<String>()
+        self.sequences.iter().map(|seq| seq.raw_text()).collect()
     }
     pub fn len(&self) -> usize {
-        let raw = self.raw_text();
-        eprint!("{}", raw);
         let re = Regex::new(r"\x1b\[[0-9;]*[mK]").unwrap();
-        let s = re.replace_all(&raw, "");
-        UnicodeWidthStr::width(s.as_ref())
+        self.sequences
+            .iter()
+            .map(|seq| match seq {
+                // Private-use glyphs have no Unicode width, so use the icon's own
+                ZshSequence::Icon(icon, set) => icon.width(*set),
+                _ => UnicodeWidthStr::width(re.replace_all(&seq.raw_text(), "").as_ref()),
+            })
+            .sum()
     }
     pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
+
+    /// Applies `f` to every foreground and background color in the prompt.
+    pub fn map_colors(mut self, f: impl Fn(NamedColor) -> NamedColor) -> Self {
+        map_sequence_colors(&mut self.sequences, &f);
+        self
+    }
+
+    /// Previews the prompt as seen with the given color vision deficiency.
+    pub fn simulate_color_blindness(self, kind: ColorBlindness) -> Self {
+        self.map_colors(|color| kind.simulate(color))
+    }
+
+    /// Replaces chromatic colors with a palette that stays distinguishable for color-blind users.
+    pub fn with_accessible_palette(self) -> Self {
+        self.map_colors(accessibility::accessible_color)
+    }
+
+    /// Checks the prompt for problems such as unreadable foreground/background pairs.
+    pub fn validate(&self, options: &ValidationOptions) -> Vec<ValidationIssue> {
+        validation::validate(&self.sequences, options)
+    }
+}
+
+fn map_sequence_colors(sequences: &mut [ZshSequence], f: &impl Fn(NamedColor) -> NamedColor) {
+    for seq in sequences.iter_mut() {
+        match seq {
+            ZshSequence::ForegroundColor(color) | ZshSequence::BackgroundColor(color) => {
+                *color = f(*color);
+            }
+            ZshSequence::Conditional {
+                when_true,
+                when_false,
+                ..
+            } => {
+                map_sequence_colors(when_true, f);
+                map_sequence_colors(when_false, f);
+            }
+            _ => {}
+        }
+    }
+}
+
+fn set_sequence_icon_set(sequences: &mut [ZshSequence], icon_set: IconSet) {
+    for seq in sequences.iter_mut() {
+        match seq {
+            ZshSequence::Icon(_, set) | ZshSequence::IconSpace(set) => *set = icon_set,
+            ZshSequence::Conditional {
+                when_true,
+                when_false,
+                ..
+            } => {
+                set_sequence_icon_set(when_true, icon_set);
+                set_sequence_icon_set(when_false, icon_set);
+            }
+            _ => {}
+        }
+    }
 }
 
 #[cfg(test)]
@@ -293,4 +491,151 @@ mod tests {
         // 期待される出力: %{%F{blue}%}[%{%f%}%n@%m%{%F{blue}%}]%{%f%}
         assert_eq!(prompt, "%{%F{blue}%}[%{%f%}%n@%m%{%F{blue}%}]%{%f%}");
     }
+
+    #[test]
+    fn test_builder_validate_contrast() {
+        let builder = ZshPromptBuilder::new()
+            .color_bg(NamedColor::LightWhite)
+            .color(NamedColor::LightYellow)
+            .str("unreadable");
+        assert_eq!(builder.validate(&ValidationOptions::default()).len(), 1);
+
+        let fixed = ZshPromptBuilder::new()
+            .color_bg_auto_fg(NamedColor::LightWhite)
+            .str("readable");
+        assert!(fixed.validate(&ValidationOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_builder_accessible_palette() {
+        let prompt = ZshPromptBuilder::new()
+            .color(NamedColor::Red)
+            .str("x")
+            .color(NamedColor::Green)
+            .str("y")
+            .with_accessible_palette()
+            .build();
+        assert_eq!(prompt, "%{\x1b[38;2;213;94;0m%}x%{\x1b[38;2;0;158;115m%}y");
+    }
+
+    #[test]
+    fn test_builder_simulate_color_blindness_keeps_text() {
+        let builder = ZshPromptBuilder::new()
+            .color_bg(NamedColor::Green)
+            .color(NamedColor::Red)
+            .str("status")
+            .simulate_color_blindness(ColorBlindness::Protanopia);
+        assert_eq!(builder.text(), "status");
+        assert!(!builder.build().contains("%F{red}"));
+    }
+
+    #[test]
+    fn test_builder_build_with_monochrome() {
+        let builder = ZshPromptBuilder::new()
+            .bold()
+            .color(NamedColor::Red)
+            .color_bg(NamedColor::White)
+            .str("x")
+            .end_color_bg()
+            .end_color()
+            .end_bold()
+            .reset_styles();
+        assert_eq!(
+            builder.build_with(&BuildOptions::monochrome()),
+            "%{%B%}x%{%b%}%{\x1b[0m%}"
+        );
+        assert_eq!(builder.build_with(&BuildOptions::plain()), "x");
+        assert_eq!(
+            builder.build_with(&BuildOptions::default()),
+            builder.build()
+        );
+    }
+
+    #[test]
+    fn test_builder_strip_colors() {
+        let prompt = ZshPromptBuilder::new()
+            .underline()
+            .color(NamedColor::Green)
+            .username()
+            .end_color()
+            .end_underline()
+            .strip_colors(false)
+            .build();
+        assert_eq!(prompt, "%n");
+    }
+
+    #[test]
+    fn test_builder_build_for_bash() {
+        let builder = ZshPromptBuilder::new()
+            .color(NamedColor::Green)
+            .username()
+            .str("@")
+            .hostname_short()
+            .end_color()
+            .str(" ")
+            .current_dir_tilde()
+            .str(" ")
+            .privileged_indicator();
+        assert_eq!(
+            builder.build_for(Shell::Bash),
+            "\\[\\e[32m\\]\\u@\\h\\[\\e[39m\\] \\w \\$"
+        );
+        assert_eq!(builder.build_for(Shell::Zsh), builder.build());
+    }
+
+    #[test]
+    fn test_builder_build_ansi_and_tmux() {
+        let ctx = PromptContext {
+            username: "alice".to_string(),
+            cwd: "/home/alice/src".into(),
+            home: Some("/home/alice".into()),
+            ..Default::default()
+        };
+        let builder = ZshPromptBuilder::new()
+            .color(NamedColor::Blue)
+            .username()
+            .end_color()
+            .str(":")
+            .current_dir_tilde();
+        assert_eq!(builder.build_ansi(&ctx), "\x1b[34malice\x1b[39m:~/src");
+        assert_eq!(
+            builder.build_tmux(&ctx),
+            "#[fg=blue]alice#[fg=default]:~/src"
+        );
+    }
+
+    #[test]
+    fn test_builder_conditional() {
+        let builder = ZshPromptBuilder::new().conditional(
+            ZshCondition::ExitStatus(0),
+            ZshPromptBuilder::new().color(NamedColor::Green).str("ok"),
+            ZshPromptBuilder::new().color(NamedColor::Red).exit_status(),
+        );
+        assert_eq!(builder.build(), "%(?.%{%F{green}%}ok.%{%F{red}%}%?)");
+        assert_eq!(builder.strip_colors(true).build(), "%(?.ok.%?)");
+    }
+
+    #[test]
+    fn test_builder_icon_set() {
+        let builder = ZshPromptBuilder::new()
+            .icon(Icon::Python)
+            .str("venv")
+            .with_icon_set(IconSet::NerdFont);
+        assert_eq!(builder.build(), "%{%G\u{e73c}%} venv");
+        assert_eq!(builder.len(), 6);
+        let builder = builder.with_icon_set(IconSet::Unicode);
+        assert_eq!(builder.text(), "🐍 venv");
+        assert_eq!(builder.len(), 7);
+        assert_eq!(
+            builder
+                .clone()
+                .with_icon_set(IconSet::Ascii)
+                .build_for(Shell::Bash),
+            "py venv"
+        );
+        // アイコンなしでは区切りの空白も出さない
+        let builder = builder.with_icon_set(IconSet::None);
+        assert_eq!(builder.build(), "venv");
+        assert_eq!(builder.len(), 4);
+    }
 }
@@ -1,4 +1,5 @@
 use std::fmt;
+use std::str::FromStr;
 
 /// Represents a color for Zsh prompt sequences (named colors or 256-color codes).
 #[derive(Debug, Clone, PartialEq, Eq, Copy)]
@@ -82,5 +83,280 @@ impl NamedColor {
     }
 }
 
+impl NamedColor {
+    /// Converts the color to ANSI SGR parameters (without the `ESC[` prefix and `m` suffix).
+    ///
+    /// Named colors follow `to_zsh_string`, so `LightBlack` is 256-color code 240.
+    pub fn to_ansi_sgr(&self, background: bool) -> String {
+        let base = match self {
+            NamedColor::Black => 30,
+            NamedColor::Red => 31,
+            NamedColor::Green => 32,
+            NamedColor::Yellow => 33,
+            NamedColor::Blue => 34,
+            NamedColor::Magenta => 35,
+            NamedColor::Cyan => 36,
+            NamedColor::White | NamedColor::LightWhite => 37,
+            NamedColor::LightRed => 91,
+            NamedColor::LightGreen => 92,
+            NamedColor::LightYellow => 93,
+            NamedColor::LightBlue => 94,
+            NamedColor::LightMagenta => 95,
+            NamedColor::LightCyan => 96,
+            NamedColor::LightBlack => {
+                return NamedColor::Code256(240).to_ansi_sgr(background);
+            }
+            NamedColor::Code256(code) => {
+                return format!("{};5;{}", if background { 48 } else { 38 }, code);
+            }
+            NamedColor::FullColor((r, g, b)) => {
+                return format!("{};2;{};{};{}", if background { 48 } else { 38 }, r, g, b);
+            }
+        };
+        (if background { base + 10 } else { base }).to_string()
+    }
+}
+
+/// Minimum contrast ratio recommended by WCAG 2.x (level AA) for normal text.
+pub const WCAG_AA_CONTRAST: f64 = 4.5;
+/// Minimum contrast ratio recommended by WCAG 2.x (level AAA) for normal text.
+pub const WCAG_AAA_CONTRAST: f64 = 7.0;
+
+impl NamedColor {
+    /// Approximates the color as an sRGB triple using the xterm default palette.
+    ///
+    /// Named colors follow what `to_zsh_string` asks the terminal for, so
+    /// `LightBlack` resolves to 256-color code 240 and `LightWhite` to `white`.
+    pub fn to_rgb(&self) -> (u8, u8, u8) {
+        match self {
+            NamedColor::Black => (0, 0, 0),
+            NamedColor::Red => (205, 0, 0),
+            NamedColor::Green => (0, 205, 0),
+            NamedColor::Yellow => (205, 205, 0),
+            NamedColor::Blue => (0, 0, 238),
+            NamedColor::Magenta => (205, 0, 205),
+            NamedColor::Cyan => (0, 205, 205),
+            NamedColor::White | NamedColor::LightWhite => (229, 229, 229),
+            NamedColor::LightBlack => NamedColor::Code256(240).to_rgb(),
+            NamedColor::LightRed => (255, 0, 0),
+            NamedColor::LightGreen => (0, 255, 0),
+            NamedColor::LightYellow => (255, 255, 0),
+            NamedColor::LightBlue => (92, 92, 255),
+            NamedColor::LightMagenta => (255, 0, 255),
+            NamedColor::LightCyan => (0, 255, 255),
+            NamedColor::Code256(code) => xterm_256_to_rgb(*code),
+            NamedColor::FullColor(rgb) => *rgb,
+        }
+    }
+
+    /// Relative luminance as defined by WCAG 2.x (0.0 for black, 1.0 for white).
+    pub fn relative_luminance(&self) -> f64 {
+        let (r, g, b) = self.to_rgb();
+        let channel = |c: u8| {
+            let c = c as f64 / 255.0;
+            if c <= 0.03928 {
+                c / 12.92
+            } else {
+                ((c + 0.055) / 1.055).powf(2.4)
+            }
+        };
+        0.2126 * channel(r) + 0.7152 * channel(g) + 0.0722 * channel(b)
+    }
+
+    /// WCAG contrast ratio between two colors, ranging from 1.0 to 21.0.
+    pub fn contrast_ratio(&self, other: &NamedColor) -> f64 {
+        let a = self.relative_luminance();
+        let b = other.relative_luminance();
+        let (lighter, darker) = if a > b { (a, b) } else { (b, a) };
+        (lighter + 0.05) / (darker + 0.05)
+    }
+
+    /// Picks black or white text, whichever reads better on the given background.
+    pub fn auto_contrast_fg(bg: NamedColor) -> NamedColor {
+        if bg.contrast_ratio(&NamedColor::Black) >= bg.contrast_ratio(&NamedColor::LightWhite) {
+            NamedColor::Black
+        } else {
+            NamedColor::LightWhite
+        }
+    }
+}
+
+/// Converts an xterm 256-color code to sRGB.
+fn xterm_256_to_rgb(code: u8) -> (u8, u8, u8) {
+    const BASE: [NamedColor; 16] = [
+        NamedColor::Black,
+        NamedColor::Red,
+        NamedColor::Green,
+        NamedColor::Yellow,
+        NamedColor::Blue,
+        NamedColor::Magenta,
+        NamedColor::Cyan,
+        NamedColor::White,
+        NamedColor::FullColor((127, 127, 127)),
+        NamedColor::LightRed,
+        NamedColor::LightGreen,
+        NamedColor::LightYellow,
+        NamedColor::LightBlue,
+        NamedColor::LightMagenta,
+        NamedColor::LightCyan,
+        NamedColor::FullColor((255, 255, 255)),
+    ];
+    match code {
+        0..=15 => BASE[code as usize].to_rgb(),
+        16..=231 => {
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            let index = code - 16;
+            (level(index / 36), level((index / 6) % 6), level(index % 6))
+        }
+        232..=255 => {
+            let gray = 8 + (code - 232) * 10;
+            (gray, gray, gray)
+        }
+    }
+}
+
+/// Error returned when a string does not name a color.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseColorError(pub String);
+
+impl fmt::Display for ParseColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "invalid color `{}` (expected a name, 0-255 or #rrggbb)",
+            self.0
+        )
+    }
+}
+
+impl std::error::Error for ParseColorError {}
+
+impl FromStr for NamedColor {
+    type Err = ParseColorError;
+
+    /// Parses a color name (`red`, `light-blue`, `bright_cyan`), a 256-color code
+    /// (`208`) or a hex RGB value (`#f80`, `#ff8800`).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let err = || ParseColorError(s.to_string());
+        let trimmed = s.trim();
+        if let Some(hex) = trimmed.strip_prefix('#') {
+            if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(err());
+            }
+            let channel = |i: usize, len: usize| u8::from_str_radix(&hex[i..i + len], 16);
+            let rgb = match hex.len() {
+                // #rgb は各桁を 2 回繰り返した値
+                3 => (channel(0, 1), channel(1, 1), channel(2, 1)),
+                6 => (channel(0, 2), channel(2, 2), channel(4, 2)),
+                _ => return Err(err()),
+            };
+            return match rgb {
+                (Ok(r), Ok(g), Ok(b)) if hex.len() == 3 => {
+                    Ok(NamedColor::FullColor((r * 17, g * 17, b * 17)))
+                }
+                (Ok(r), Ok(g), Ok(b)) => Ok(NamedColor::FullColor((r, g, b))),
+                _ => Err(err()),
+            };
+        }
+        if let Ok(code) = trimmed.parse::<u8>() {
+            return Ok(NamedColor::Code256(code));
+        }
+
+        let name: String = trimmed
+            .chars()
+            .filter(|c| *c != '-' && *c != '_')
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let (light, base) = match name
+            .strip_prefix("light")
+            .or_else(|| name.strip_prefix("bright"))
+        {
+            Some(base) => (true, base),
+            None => (false, name.as_str()),
+        };
+        let color = match (light, base) {
+            (false, "black") => NamedColor::Black,
+            (false, "red") => NamedColor::Red,
+            (false, "green") => NamedColor::Green,
+            (false, "yellow") => NamedColor::Yellow,
+            (false, "blue") => NamedColor::Blue,
+            (false, "magenta") => NamedColor::Magenta,
+            (false, "cyan") => NamedColor::Cyan,
+            (false, "white") => NamedColor::White,
+            (true, "black") | (false, "gray") | (false, "grey") => NamedColor::LightBlack,
+            (true, "red") => NamedColor::LightRed,
+            (true, "green") => NamedColor::LightGreen,
+            (true, "yellow") => NamedColor::LightYellow,
+            (true, "blue") => NamedColor::LightBlue,
+            (true, "magenta") => NamedColor::LightMagenta,
+            (true, "cyan") => NamedColor::LightCyan,
+            (true, "white") => NamedColor::LightWhite,
+            _ => return Err(err()),
+        };
+        Ok(color)
+    }
+}
+
 #[cfg(test)]
-mod tests {}
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_ansi_sgr() {
+        assert_eq!(NamedColor::Red.to_ansi_sgr(false), "31");
+        assert_eq!(NamedColor::LightCyan.to_ansi_sgr(true), "106");
+        assert_eq!(NamedColor::LightBlack.to_ansi_sgr(false), "38;5;240");
+        assert_eq!(
+            NamedColor::FullColor((1, 2, 3)).to_ansi_sgr(true),
+            "48;2;1;2;3"
+        );
+    }
+
+    #[test]
+    fn test_to_rgb_256_palette() {
+        assert_eq!(NamedColor::Code256(16).to_rgb(), (0, 0, 0));
+        assert_eq!(NamedColor::Code256(196).to_rgb(), (255, 0, 0));
+        assert_eq!(NamedColor::Code256(240).to_rgb(), (88, 88, 88));
+        assert_eq!(NamedColor::Code256(1).to_rgb(), NamedColor::Red.to_rgb());
+    }
+
+    #[test]
+    fn test_contrast_ratio_extremes() {
+        let black = NamedColor::FullColor((0, 0, 0));
+        let white = NamedColor::FullColor((255, 255, 255));
+        assert!((black.contrast_ratio(&white) - 21.0).abs() < 1e-9);
+        assert!((white.contrast_ratio(&white) - 1.0).abs() < 1e-9);
+        assert_eq!(black.contrast_ratio(&white), white.contrast_ratio(&black));
+    }
+
+    #[test]
+    fn test_yellow_on_white_is_unreadable() {
+        let ratio = NamedColor::LightYellow.contrast_ratio(&NamedColor::LightWhite);
+        assert!(ratio < WCAG_AA_CONTRAST);
+    }
+
+    #[test]
+    fn test_auto_contrast_fg() {
+        assert_eq!(
+            NamedColor::auto_contrast_fg(NamedColor::Yellow),
+            NamedColor::Black
+        );
+        assert_eq!(
+            NamedColor::auto_contrast_fg(NamedColor::Blue),
+            NamedColor::LightWhite
+        );
+    }
+
+    #[test]
+    fn test_parse_color() {
+        assert_eq!("red".parse(), Ok(NamedColor::Red));
+        assert_eq!("Light-Blue".parse(), Ok(NamedColor::LightBlue));
+        assert_eq!("bright_cyan".parse(), Ok(NamedColor::LightCyan));
+        assert_eq!("208".parse(), Ok(NamedColor::Code256(208)));
+        assert_eq!("#f80".parse(), Ok(NamedColor::FullColor((255, 136, 0))));
+        assert_eq!("#0a0B0c".parse(), Ok(NamedColor::FullColor((10, 11, 12))));
+        assert!("256".parse::<NamedColor>().is_err());
+        assert!("#12345".parse::<NamedColor>().is_err());
+        assert!("purple".parse::<NamedColor>().is_err());
+    }
+}
@@ -0,0 +1,680 @@
+//! TOML prompt configuration, compiled into `ZshPromptBuilder`s.
+//!
+//! ```toml
+//! icon_set = "nerd-font"
+//!
+//! [[line]]
+//! [[line.item]]
+//! segment = "directory"
+//!
+//! [[line.item]]
+//! segment = "git"
+//!
+//! [[line]]
+//! separator = ""
+//!
+//! [[line.item]]
+//! text = "❯ "
+//! fg = "green"
+//! bold = true
+//! when = "success"
+//!
+//! [[line.item]]
+//! text = "❯ "
+//! fg = "#ff5f5f"
+//! when = "failure"
+//!
+//! [right]
+//! [[right.item]]
+//! segment = "duration"
+//! ```
+
+use std::fmt;
+use std::fs;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+use toml::Spanned;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::IconSet;
+use crate::segments::SegmentRegistry;
+use crate::sequences::ZshCondition;
+
+/// Environment variable naming the configuration file.
+pub const CONFIG_ENV: &str = "ZSH_SEQ_CONFIG";
+
+/// Configuration used when the user has none.
+pub const DEFAULT_CONFIG: &str = r#"
+[[line]]
+[[line.item]]
+segment = "directory"
+
+[[line.item]]
+segment = "git"
+
+[[line.item]]
+segment = "duration"
+
+[[line]]
+separator = ""
+
+[[line.item]]
+text = "❯ "
+fg = "green"
+when = "success"
+
+[[line.item]]
+text = "❯ "
+fg = "red"
+when = "failure"
+
+[right]
+[[right.item]]
+segment = "keymap"
+
+[[right.item]]
+segment = "status"
+
+[[right.item]]
+segment = "jobs"
+
+[[right.item]]
+segment = "virtualenv"
+
+[[right.item]]
+segment = "conda"
+
+[[right.item]]
+segment = "nix_shell"
+"#;
+
+/// 1-based position in the configuration source.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Location {
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Location {
+    fn from_offset(source: &str, offset: usize) -> Self {
+        let before = &source[..offset.min(source.len())];
+        let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+        Self {
+            line: before.matches('\n').count() + 1,
+            column: before[line_start..].chars().count() + 1,
+        }
+    }
+}
+
+/// Error produced while loading or compiling a configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigError {
+    pub message: String,
+    /// Where the problem is, when it can be pinned to a place in the source.
+    pub location: Option<Location>,
+}
+
+impl ConfigError {
+    fn at(source: &str, span: Range<usize>, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            location: Some(Location::from_offset(source, span.start)),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.location {
+            Some(Location { line, column }) => {
+                write!(f, "line {}, column {}: {}", line, column, self.message)
+            }
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+/// The prompts produced from a configuration.
+#[derive(Debug, Clone)]
+pub struct PromptSet {
+    /// `PROMPT`, with configured lines separated by newlines.
+    pub left: ZshPromptBuilder,
+    /// `RPROMPT`, if configured.
+    pub right: Option<ZshPromptBuilder>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawConfig {
+    icon_set: Option<RawIconSet>,
+    #[serde(default)]
+    line: Vec<RawLine>,
+    right: Option<RawLine>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+enum RawIconSet {
+    NerdFont,
+    Unicode,
+    Ascii,
+    None,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawLine {
+    separator: Option<String>,
+    #[serde(default)]
+    item: Vec<Spanned<RawItem>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct RawItem {
+    segment: Option<Spanned<String>>,
+    text: Option<String>,
+    value: Option<Value>,
+    fg: Option<Spanned<String>>,
+    bg: Option<Spanned<String>>,
+    #[serde(default)]
+    bold: bool,
+    #[serde(default)]
+    underline: bool,
+    #[serde(default)]
+    standout: bool,
+    when: Option<When>,
+}
+
+/// Prompt values an item can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Value {
+    Username,
+    Hostname,
+    Directory,
+    DirectoryFull,
+    Privileged,
+    ExitStatus,
+}
+
+/// Conditions under which an item is shown.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum When {
+    Success,
+    Failure,
+    Root,
+    User,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Content {
+    Segment(String),
+    Text(String),
+    Value(Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Item {
+    content: Content,
+    /// Position of the segment name, used to report unknown segments.
+    location: Location,
+    fg: Option<NamedColor>,
+    bg: Option<NamedColor>,
+    bold: bool,
+    underline: bool,
+    standout: bool,
+    when: Option<When>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Line {
+    separator: String,
+    items: Vec<Item>,
+}
+
+/// A parsed and validated prompt configuration.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PromptConfig {
+    icon_set: IconSet,
+    lines: Vec<Line>,
+    right: Option<Line>,
+}
+
+impl PromptConfig {
+    /// The builtin `DEFAULT_CONFIG`.
+    pub fn builtin() -> Self {
+        Self::parse(DEFAULT_CONFIG).expect("the default configuration is valid")
+    }
+
+    /// Where the user's configuration lives: `$ZSH_SEQ_CONFIG`, otherwise
+    /// `zsh_seq/config.toml` under `$XDG_CONFIG_HOME` (default `~/.config`).
+    pub fn user_path(ctx: &PromptContext) -> Option<PathBuf> {
+        if let Some(path) = ctx.var(CONFIG_ENV) {
+            return Some(PathBuf::from(path));
+        }
+        let config_home = match ctx.var("XDG_CONFIG_HOME") {
+            Some(dir) => PathBuf::from(dir),
+            None => ctx.home.as_ref()?.join(".config"),
+        };
+        Some(config_home.join("zsh_seq").join("config.toml"))
+    }
+
+    /// Loads the user's configuration, or `builtin()` if the default location
+    /// has no file. A file named by `$ZSH_SEQ_CONFIG` must exist.
+    pub fn load_user(ctx: &PromptContext) -> Result<Self, ConfigError> {
+        match Self::user_path(ctx) {
+            Some(path) if ctx.var(CONFIG_ENV).is_some() || path.exists() => Self::load(&path),
+            _ => Ok(Self::builtin()),
+        }
+    }
+
+    /// Reads and parses a configuration file.
+    pub fn load(path: &Path) -> Result<Self, ConfigError> {
+        let source = fs::read_to_string(path).map_err(|err| ConfigError {
+            message: format!("{}: {}", path.display(), err),
+            location: None,
+        })?;
+        Self::parse(&source)
+    }
+
+    /// Parses a configuration, rejecting unknown keys, colors and values.
+    pub fn parse(source: &str) -> Result<Self, ConfigError> {
+        let raw: RawConfig = toml::from_str(source).map_err(|err| ConfigError {
+            message: err.message().to_string(),
+            location: err
+                .span()
+                .map(|span| Location::from_offset(source, span.start)),
+        })?;
+        let icon_set = match raw.icon_set {
+            Some(RawIconSet::NerdFont) => IconSet::NerdFont,
+            Some(RawIconSet::Unicode) | None => IconSet::Unicode,
+            Some(RawIconSet::Ascii) => IconSet::Ascii,
+            Some(RawIconSet::None) => IconSet::None,
+        };
+        let lines = raw
+            .line
+            .into_iter()
+            .map(|line| parse_line(source, line))
+            .collect::<Result<_, _>>()?;
+        let right = raw.right.map(|line| parse_line(source, line)).transpose()?;
+        Ok(Self {
+            icon_set,
+            lines,
+            right,
+        })
+    }
+
+    /// Renders the configured segments and assembles the prompts.
+    pub fn compile(
+        &self,
+        registry: &SegmentRegistry,
+        ctx: &PromptContext,
+    ) -> Result<PromptSet, ConfigError> {
+        Ok(PromptSet {
+            left: self.compile_left(registry, ctx)?,
+            right: self.compile_right(registry, ctx)?,
+        })
+    }
+
+    /// Renders only the left prompt, skipping the segments of the right one.
+    pub fn compile_left(
+        &self,
+        registry: &SegmentRegistry,
+        ctx: &PromptContext,
+    ) -> Result<ZshPromptBuilder, ConfigError> {
+        let mut left = ZshPromptBuilder::new();
+        for (i, line) in self.lines.iter().enumerate() {
+            if i > 0 {
+                left = left.newline();
+            }
+            left = left.connect(compile_line(line, registry, ctx)?);
+        }
+        Ok(left.with_icon_set(self.icon_set))
+    }
+
+    /// Renders only the right prompt, if one is configured.
+    pub fn compile_right(
+        &self,
+        registry: &SegmentRegistry,
+        ctx: &PromptContext,
+    ) -> Result<Option<ZshPromptBuilder>, ConfigError> {
+        let right = self
+            .right
+            .as_ref()
+            .map(|line| compile_line(line, registry, ctx))
+            .transpose()?;
+        Ok(right.map(|right| right.with_icon_set(self.icon_set)))
+    }
+}
+
+fn parse_line(source: &str, line: RawLine) -> Result<Line, ConfigError> {
+    let items = line
+        .item
+        .into_iter()
+        .map(|item| parse_item(source, item))
+        .collect::<Result<_, _>>()?;
+    Ok(Line {
+        separator: line.separator.unwrap_or_else(|| " ".to_string()),
+        items,
+    })
+}
+
+fn parse_item(source: &str, item: Spanned<RawItem>) -> Result<Item, ConfigError> {
+    let span = item.span();
+    let raw = item.into_inner();
+    let mut location = Location::from_offset(source, span.start);
+    let content = match (raw.segment, raw.text, raw.value) {
+        (Some(segment), None, None) => {
+            location = Location::from_offset(source, segment.span().start);
+            Content::Segment(segment.into_inner())
+        }
+        (None, Some(text), None) => Content::Text(text),
+        (None, None, Some(value)) => Content::Value(value),
+        _ => {
+            return Err(ConfigError::at(
+                source,
+                span,
+                "an item needs exactly one of `segment`, `text` or `value`",
+            ));
+        }
+    };
+    let color = |value: Option<Spanned<String>>| -> Result<Option<NamedColor>, ConfigError> {
+        value
+            .map(|value| {
+                value
+                    .get_ref()
+                    .parse()
+                    .map_err(|err| ConfigError::at(source, value.span(), format!("{}", err)))
+            })
+            .transpose()
+    };
+    Ok(Item {
+        content,
+        location,
+        fg: color(raw.fg)?,
+        bg: color(raw.bg)?,
+        bold: raw.bold,
+        underline: raw.underline,
+        standout: raw.standout,
+        when: raw.when,
+    })
+}
+
+fn compile_line(
+    line: &Line,
+    registry: &SegmentRegistry,
+    ctx: &PromptContext,
+) -> Result<ZshPromptBuilder, ConfigError> {
+    let mut parts = Vec::with_capacity(line.items.len());
+    for item in &line.items {
+        if let Some(part) = compile_item(item, registry, ctx)? {
+            parts.push(part);
+        }
+    }
+    let separator = ZshPromptBuilder::new().str(&line.separator);
+    Ok(ZshPromptBuilder::new().join(parts, &separator))
+}
+
+fn compile_item(
+    item: &Item,
+    registry: &SegmentRegistry,
+    ctx: &PromptContext,
+) -> Result<Option<ZshPromptBuilder>, ConfigError> {
+    let content = match &item.content {
+        Content::Segment(name) => {
+            let segment = registry.get(name).ok_or_else(|| ConfigError {
+                message: format!(
+                    "unknown segment `{}`, expected one of: {}",
+                    name,
+                    registry.names().join(", ")
+                ),
+                location: Some(item.location),
+            })?;
+            match segment.render(ctx) {
+                Some(builder) => builder,
+                None => return Ok(None),
+            }
+        }
+        Content::Text(text) => ZshPromptBuilder::new().str(text),
+        Content::Value(value) => {
+            let builder = ZshPromptBuilder::new();
+            match value {
+                Value::Username => builder.username(),
+                Value::Hostname => builder.hostname_short(),
+                Value::Directory => builder.current_dir_tilde(),
+                Value::DirectoryFull => builder.current_dir_full(),
+                Value::Privileged => builder.privileged_indicator(),
+                Value::ExitStatus => builder.exit_status(),
+            }
+        }
+    };
+
+    let mut builder = ZshPromptBuilder::new();
+    if let Some(bg) = item.bg {
+        builder = builder.color_bg(bg);
+    }
+    if let Some(fg) = item.fg {
+        builder = builder.color(fg);
+    }
+    if item.bold {
+        builder = builder.bold();
+    }
+    if item.underline {
+        builder = builder.underline();
+    }
+    if item.standout {
+        builder = builder.standout();
+    }
+    builder = builder.connect(content);
+    if item.standout {
+        builder = builder.end_standout();
+    }
+    if item.underline {
+        builder = builder.end_underline();
+    }
+    if item.bold {
+        builder = builder.end_bold();
+    }
+    if item.fg.is_some() {
+        builder = builder.end_color();
+    }
+    if item.bg.is_some() {
+        builder = builder.end_color_bg();
+    }
+
+    let empty = ZshPromptBuilder::new();
+    let builder = match item.when {
+        None => builder,
+        Some(When::Success) => {
+            empty
+                .clone()
+                .conditional(ZshCondition::ExitStatus(0), builder, empty)
+        }
+        Some(When::Failure) => {
+            empty
+                .clone()
+                .conditional(ZshCondition::ExitStatus(0), empty, builder)
+        }
+        Some(When::Root) => empty
+            .clone()
+            .conditional(ZshCondition::Privileged, builder, empty),
+        Some(When::User) => empty
+            .clone()
+            .conditional(ZshCondition::Privileged, empty, builder),
+    };
+    Ok(Some(builder))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn compile(source: &str) -> Result<PromptSet, ConfigError> {
+        PromptConfig::parse(source)?
+            .compile(&SegmentRegistry::with_builtins(), &PromptContext::default())
+    }
+
+    #[test]
+    fn test_compile_lines_and_conditions() {
+        let prompts = compile(
+            r##"
+[[line]]
+[[line.item]]
+value = "username"
+fg = "blue"
+bold = true
+
+[[line.item]]
+value = "directory"
+
+[[line]]
+[[line.item]]
+text = ">"
+fg = "#0f0"
+when = "success"
+
+[[line.item]]
+text = "!"
+fg = "red"
+when = "failure"
+
+[right]
+[[right.item]]
+value = "exit_status"
+"##,
+        )
+        .unwrap();
+        assert_eq!(
+            prompts.left.build(),
+            "%{%F{blue}%}%{%B%}%n%{%b%}%{%f%} %~\n\
+             %(?.%{\x1b[38;2;0;255;0m%}>%{%f%}.) %(?..%{%F{red}%}!%{%f%})"
+        );
+        assert_eq!(prompts.right.unwrap().build(), "%?");
+    }
+
+    #[test]
+    fn test_hidden_segments_are_skipped() {
+        let prompts = compile(
+            r#"
+[[line]]
+separator = " | "
+[[line.item]]
+segment = "virtualenv"
+
+[[line.item]]
+text = "a"
+
+[[line.item]]
+text = "b"
+"#,
+        )
+        .unwrap();
+        assert_eq!(prompts.left.build(), "a | b");
+        assert!(prompts.right.is_none());
+    }
+
+    #[test]
+    fn test_icon_set() {
+        let prompts = PromptConfig::parse(
+            r#"
+icon_set = "ascii"
+[[line]]
+[[line.item]]
+segment = "virtualenv"
+"#,
+        )
+        .unwrap()
+        .compile(
+            &SegmentRegistry::with_builtins(),
+            &PromptContext {
+                env: [("VIRTUAL_ENV".to_string(), "/src/.venv".to_string())].into(),
+                ..Default::default()
+            },
+        )
+        .unwrap();
+        assert_eq!(prompts.left.text(), "py .venv");
+    }
+
+    #[test]
+    fn test_errors_have_locations() {
+        let err = PromptConfig::parse("[[line]]\n[[line.item]]\ntext = \"x\"\ncolour = \"red\"\n")
+            .unwrap_err();
+        assert_eq!(err.location, Some(Location { line: 4, column: 1 }));
+        assert!(err.message.contains("unknown field `colour`"));
+
+        let err = PromptConfig::parse("[[line]]\n[[line.item]]\ntext = \"x\"\nfg = \"purple\"\n")
+            .unwrap_err();
+        assert_eq!(err.location, Some(Location { line: 4, column: 6 }));
+        assert_eq!(
+            err.to_string(),
+            "line 4, column 6: invalid color `purple` (expected a name, 0-255 or #rrggbb)"
+        );
+
+        let err = PromptConfig::parse("icon_set = \"emoji\"\n").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(Location {
+                line: 1,
+                column: 12
+            })
+        );
+
+        let err = compile("[[line]]\n[[line.item]]\n  segment = \"gti\"\n").unwrap_err();
+        assert_eq!(
+            err.location,
+            Some(Location {
+                line: 3,
+                column: 13
+            })
+        );
+        assert!(err.message.starts_with("unknown segment `gti`"));
+
+        let err = PromptConfig::parse("[[line]]\n[[line.item]]\nbold = true\n").unwrap_err();
+        assert_eq!(err.location.map(|l| l.line), Some(2));
+    }
+
+    #[test]
+    fn test_default_config() {
+        let prompts = PromptConfig::builtin()
+            .compile(&SegmentRegistry::with_builtins(), &PromptContext::default())
+            .unwrap();
+        assert!(
+            prompts
+                .left
+                .build()
+                .ends_with("\n%(?.%{%F{green}%}%{%G❯%} %{%f%}.)%(?..%{%F{red}%}%{%G❯%} %{%f%})")
+        );
+        assert!(prompts.right.is_some());
+    }
+
+    #[test]
+    fn test_user_path() {
+        let mut ctx = PromptContext {
+            home: Some(PathBuf::from("/home/me")),
+            ..Default::default()
+        };
+        assert_eq!(
+            PromptConfig::user_path(&ctx),
+            Some(PathBuf::from("/home/me/.config/zsh_seq/config.toml"))
+        );
+        ctx.env.insert("XDG_CONFIG_HOME".into(), "/xdg".into());
+        assert_eq!(
+            PromptConfig::user_path(&ctx),
+            Some(PathBuf::from("/xdg/zsh_seq/config.toml"))
+        );
+        // 既定の場所にファイルが無ければ組み込みの設定を使う
+        assert_eq!(PromptConfig::load_user(&ctx), Ok(PromptConfig::builtin()));
+
+        ctx.env
+            .insert(CONFIG_ENV.into(), "/nonexistent/prompt.toml".into());
+        assert!(PromptConfig::load_user(&ctx).is_err());
+    }
+}
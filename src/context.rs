@@ -0,0 +1,156 @@
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Environment variable the generated hooks export with the elapsed milliseconds.
+pub const DURATION_ENV: &str = "ZSH_SEQ_DURATION_MS";
+
+/// Values that dynamic sequences (`%n`, `%m`, `%~`, ...) resolve to when a prompt
+/// is rendered outside of zsh.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct PromptContext {
+    pub username: String,
+    pub hostname: String,
+    pub cwd: PathBuf,
+    pub home: Option<PathBuf>,
+    pub privileged: bool,
+    /// How long the last command ran, if known.
+    pub duration: Option<Duration>,
+    /// Exit status of the last command, if known.
+    pub status: Option<i32>,
+    /// Exit statuses of every command in the last pipeline (`$pipestatus`).
+    pub pipestatus: Vec<i32>,
+    /// Number of background jobs (`${#jobstates}`).
+    pub jobs: usize,
+    /// Width of the terminal (`$COLUMNS`), if known.
+    pub columns: Option<usize>,
+    /// Active zle keymap (`$KEYMAP`), e.g. `main` or `vicmd`.
+    pub keymap: Option<String>,
+    /// Named directories (`hash -d name=path`), shown as `~name`.
+    pub named_dirs: Vec<(String, PathBuf)>,
+    /// Environment variables visible to segments.
+    pub env: BTreeMap<String, String>,
+}
+
+impl PromptContext {
+    /// Collects the context from the running process.
+    pub fn current() -> Self {
+        Self {
+            username: current_username(),
+            hostname: current_hostname(),
+            cwd: current_dir(),
+            home: home_dir(),
+            privileged: is_privileged(),
+            duration: env::var(DURATION_ENV)
+                .ok()
+                .and_then(|ms| ms.parse().ok())
+                .map(Duration::from_millis),
+            status: None,
+            pipestatus: Vec::new(),
+            jobs: 0,
+            columns: env::var("COLUMNS").ok().and_then(|c| c.parse().ok()),
+            keymap: None,
+            named_dirs: Vec::new(),
+            // UTF-8 でない変数は読めないので無視する
+            env: env::vars_os()
+                .filter_map(|(key, value)| {
+                    Some((key.into_string().ok()?, value.into_string().ok()?))
+                })
+                .collect(),
+        }
+    }
+
+    /// Looks up an environment variable, treating empty values as unset.
+    pub fn var(&self, key: &str) -> Option<&str> {
+        self.env
+            .get(key)
+            .map(String::as_str)
+            .filter(|value| !value.is_empty())
+    }
+
+    /// The working directory with the home directory replaced by `~`, like `%~`.
+    pub fn cwd_tilde(&self) -> String {
+        tilde_path(&self.cwd, self.home.as_deref())
+    }
+
+    /// The working directory without tilde substitution, like `%/`.
+    pub fn cwd_full(&self) -> String {
+        display_path(&self.cwd)
+    }
+}
+
+pub(crate) fn current_username() -> String {
+    users::get_current_username()
+        .and_then(|name| name.into_string().ok())
+        .or_else(|| env::var("USER").ok())
+        .unwrap_or_default()
+}
+
+/// ホスト名: 最初のドットまで
+pub(crate) fn current_hostname() -> String {
+    hostname::get()
+        .map(|h| {
+            h.to_string_lossy()
+                .split('.')
+                .next()
+                .unwrap_or("localhost")
+                .to_string()
+        })
+        .unwrap_or_else(|_| {
+            env::var("HOSTNAME")
+                .map(|h| h.split('.').next().unwrap_or("localhost").to_string())
+                .unwrap_or_else(|_| "localhost".to_string())
+        })
+}
+
+pub(crate) fn current_dir() -> PathBuf {
+    env::current_dir().unwrap_or_else(|_| PathBuf::from("."))
+}
+
+pub(crate) fn home_dir() -> Option<PathBuf> {
+    env::var("HOME").map(PathBuf::from).ok()
+}
+
+/// UIDが0なら特権ユーザー (Unix系前提)
+pub(crate) fn is_privileged() -> bool {
+    users::get_current_uid() == 0
+}
+
+pub(crate) fn display_path(path: &Path) -> String {
+    let s = path.display().to_string();
+    let trimmed = s.trim_end_matches('/');
+    if trimmed.is_empty() && s.starts_with('/') {
+        "/".to_string()
+    } else {
+        trimmed.to_string()
+    }
+}
+
+pub(crate) fn tilde_path(path: &Path, home: Option<&Path>) -> String {
+    if let Some(h) = home
+        && let Ok(stripped) = path.strip_prefix(h)
+    {
+        return format!("~/{}", stripped.display())
+            .trim_end_matches('/')
+            .to_string();
+    }
+    display_path(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tilde_path() {
+        let home = Path::new("/home/user");
+        assert_eq!(tilde_path(Path::new("/home/user"), Some(home)), "~");
+        assert_eq!(
+            tilde_path(Path::new("/home/user/src/"), Some(home)),
+            "~/src"
+        );
+        assert_eq!(tilde_path(Path::new("/etc"), Some(home)), "/etc");
+        assert_eq!(tilde_path(Path::new("/"), None), "/");
+    }
+}
@@ -0,0 +1,702 @@
+//! Annotates an arbitrary zsh prompt string, for `zsh_seq_cli explain`.
+//!
+//! The prompt is split into tokens (text, `%` escapes, `%{ %}` markers and raw
+//! terminal escape codes). Each token gets a description, its value in a
+//! `PromptContext` and the number of cells it occupies on screen. Mismatches
+//! between what the terminal draws and what zsh counts, which make the cursor
+//! and `RPROMPT` land in the wrong column, are reported as issues.
+
+use std::fmt;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::context::PromptContext;
+
+/// One piece of a prompt string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    /// The source text, e.g. `%F{red}`.
+    pub source: String,
+    /// Position of the token in characters, counted from 1.
+    pub column: usize,
+    pub meaning: String,
+    /// What the token prints, or `None` if it cannot be known outside zsh.
+    pub value: Option<String>,
+    /// Cells the token occupies on screen, if known.
+    pub width: Option<usize>,
+    /// A zero-width marker problem or malformed escape.
+    pub issue: Option<String>,
+}
+
+/// A prompt split into annotated tokens. `Display` prints it as a table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Explanation {
+    pub tokens: Vec<Token>,
+}
+
+impl Explanation {
+    /// Total display width, or `None` if some value is unknown.
+    pub fn width(&self) -> Option<usize> {
+        self.tokens.iter().map(|token| token.width).sum()
+    }
+
+    pub fn issues(&self) -> impl Iterator<Item = &Token> {
+        self.tokens.iter().filter(|token| token.issue.is_some())
+    }
+}
+
+/// Splits `prompt` into tokens, resolving dynamic values from `ctx`.
+pub fn explain(prompt: &str, ctx: &PromptContext) -> Explanation {
+    let mut parser = Parser {
+        chars: prompt.chars().collect(),
+        pos: 0,
+        ctx,
+        tokens: Vec::new(),
+        group: None,
+    };
+    parser.parse();
+    Explanation {
+        tokens: parser.tokens,
+    }
+}
+
+/// Shows control characters as `\e`, `\n` or `\xNN`.
+fn printable(s: &str) -> String {
+    let mut result = String::new();
+    for c in s.chars() {
+        match c {
+            '\x1b' => result.push_str(r"\e"),
+            '\n' => result.push_str(r"\n"),
+            '\t' => result.push_str(r"\t"),
+            c if c.is_control() => result.push_str(&format!(r"\x{:02x}", c as u32)),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Keeps the last `n` path components (or the first `-n` when negative), like
+/// the numeric argument of `%~` and `%/`.
+fn path_components(path: &str, n: Option<i32>) -> String {
+    let Some(n) = n.filter(|n| *n != 0) else {
+        return path.to_string();
+    };
+    let parts: Vec<&str> = path.split('/').filter(|p| !p.is_empty()).collect();
+    let count = n.unsigned_abs() as usize;
+    if count >= parts.len() {
+        return path.to_string();
+    }
+    if n > 0 {
+        parts[parts.len() - count..].join("/")
+    } else {
+        let head = parts[..count].join("/");
+        if path.starts_with('/') {
+            format!("/{}", head)
+        } else {
+            head
+        }
+    }
+}
+
+struct Group {
+    /// Index of the `%{` token.
+    start: usize,
+    /// Tokens of visible text inside the group.
+    text: Vec<usize>,
+    has_glyph_width: bool,
+}
+
+struct Parser<'a> {
+    chars: Vec<char>,
+    pos: usize,
+    ctx: &'a PromptContext,
+    tokens: Vec<Token>,
+    /// The open `%{`, if any.
+    group: Option<Group>,
+}
+
+impl Parser<'_> {
+    fn source(&self, start: usize) -> String {
+        self.chars[start..self.pos].iter().collect()
+    }
+
+    fn push(&mut self, start: usize, meaning: impl Into<String>, value: Option<String>) {
+        let width = value.as_deref().map(UnicodeWidthStr::width);
+        self.tokens.push(Token {
+            source: self.source(start),
+            column: start + 1,
+            meaning: meaning.into(),
+            value,
+            width,
+            issue: None,
+        });
+    }
+
+    fn issue(&mut self, issue: impl Into<String>) {
+        if let Some(token) = self.tokens.last_mut() {
+            token.issue = Some(issue.into());
+        }
+    }
+
+    /// Reads an optional (possibly negative) numeric argument.
+    fn number(&mut self) -> Option<i32> {
+        let start = self.pos;
+        if self.chars.get(self.pos) == Some(&'-') {
+            self.pos += 1;
+        }
+        while self.chars.get(self.pos).is_some_and(char::is_ascii_digit) {
+            self.pos += 1;
+        }
+        match self.source(start).as_str() {
+            "" => None,
+            "-" => Some(-1),
+            digits => digits.parse().ok(),
+        }
+    }
+
+    /// Reads a `{...}` argument if one follows.
+    fn braced(&mut self) -> Option<String> {
+        if self.chars.get(self.pos) != Some(&'{') {
+            return None;
+        }
+        let end = self.chars[self.pos..].iter().position(|c| *c == '}')?;
+        let arg = self.chars[self.pos + 1..self.pos + end].iter().collect();
+        self.pos += end + 1;
+        Some(arg)
+    }
+
+    /// Reads up to one of `ends` at the current nesting level, skipping escapes.
+    fn until(&mut self, ends: &[char]) -> Option<String> {
+        let start = self.pos;
+        let mut depth = 0;
+        while let Some(&c) = self.chars.get(self.pos) {
+            match c {
+                '%' => {
+                    if self.chars.get(self.pos + 1) == Some(&'(') {
+                        depth += 1;
+                    }
+                    self.pos = (self.pos + 2).min(self.chars.len());
+                    continue;
+                }
+                ')' if depth > 0 => depth -= 1,
+                c if depth == 0 && ends.contains(&c) => {
+                    let text = self.chars[start..self.pos].iter().collect();
+                    self.pos += 1;
+                    return Some(text);
+                }
+                _ => {}
+            }
+            self.pos += 1;
+        }
+        None
+    }
+
+    fn parse(&mut self) {
+        while let Some(&c) = self.chars.get(self.pos) {
+            let start = self.pos;
+            match c {
+                '%' => {
+                    self.pos += 1;
+                    self.escape(start);
+                }
+                '\x1b' => self.terminal_escape(start),
+                '\n' => {
+                    self.pos += 1;
+                    self.push(start, "newline", Some(String::new()));
+                }
+                c if c.is_control() => {
+                    self.pos += 1;
+                    self.push(start, "control character", Some(String::new()));
+                    if self.group.is_none() {
+                        self.issue("control character outside `%{ %}`: zsh counts it as visible");
+                    }
+                }
+                _ => {
+                    while self
+                        .chars
+                        .get(self.pos)
+                        .is_some_and(|c| *c != '%' && !c.is_control())
+                    {
+                        self.pos += 1;
+                    }
+                    let text = self.source(start);
+                    self.push(start, "text", Some(text));
+                    let index = self.tokens.len() - 1;
+                    if let Some(group) = &mut self.group {
+                        group.text.push(index);
+                    }
+                }
+            }
+        }
+        if let Some(group) = self.group.take() {
+            self.tokens[group.start].issue = Some("`%{` is never closed".to_string());
+        }
+    }
+
+    /// Parses a raw terminal escape code starting at ESC.
+    fn terminal_escape(&mut self, start: usize) {
+        self.pos += 1;
+        let mut terminated = true;
+        let meaning = match self.chars.get(self.pos) {
+            Some('[') => {
+                self.pos += 1;
+                while self
+                    .chars
+                    .get(self.pos)
+                    .is_some_and(|c| !('\x40'..='\x7e').contains(c))
+                {
+                    self.pos += 1;
+                }
+                let params: String = self.chars[start + 2..self.pos].iter().collect();
+                let kind = self.chars.get(self.pos).copied();
+                match kind {
+                    Some(_) => self.pos += 1,
+                    None => terminated = false,
+                }
+                match kind {
+                    Some('m') if params.is_empty() || params == "0" => "ANSI reset".to_string(),
+                    Some('m') => format!("ANSI style {}", params),
+                    Some('K') => "ANSI clear line".to_string(),
+                    _ => "ANSI control sequence".to_string(),
+                }
+            }
+            Some(']') => {
+                // OSC は BEL か ESC \ で終わる
+                self.pos += 1;
+                terminated = false;
+                while let Some(&c) = self.chars.get(self.pos) {
+                    self.pos += 1;
+                    if c == '\x07' {
+                        terminated = true;
+                        break;
+                    }
+                    if c == '\x1b' && self.chars.get(self.pos) == Some(&'\\') {
+                        self.pos += 1;
+                        terminated = true;
+                        break;
+                    }
+                }
+                "OSC sequence (title, hyperlink, ...)".to_string()
+            }
+            Some(_) => {
+                self.pos += 1;
+                "escape sequence".to_string()
+            }
+            None => {
+                terminated = false;
+                "lone escape character".to_string()
+            }
+        };
+        self.push(start, meaning, Some(String::new()));
+        if !terminated {
+            self.issue("unterminated escape code");
+        } else if self.group.is_none() {
+            let counted = self.pos - start - 1;
+            self.issue(format!(
+                "escape code outside `%{{ %}}`: zsh counts {} cells the terminal does not draw",
+                counted
+            ));
+        }
+    }
+
+    fn escape(&mut self, start: usize) {
+        let number = self.number();
+        let Some(&c) = self.chars.get(self.pos) else {
+            self.push(start, "incomplete escape", Some(String::new()));
+            self.issue("`%` at end of prompt (write `%%` for a literal `%`)");
+            return;
+        };
+        self.pos += 1;
+        let ctx = self.ctx;
+        let text = |s: &str| Some(s.to_string());
+        match c {
+            '{' => {
+                self.push(start, "begin zero-width (escape codes follow)", text(""));
+                if self.group.is_some() {
+                    self.issue("`%{` inside another `%{ %}`");
+                } else {
+                    self.group = Some(Group {
+                        start: self.tokens.len() - 1,
+                        text: Vec::new(),
+                        has_glyph_width: false,
+                    });
+                }
+            }
+            '}' => {
+                self.push(start, "end zero-width", text(""));
+                match self.group.take() {
+                    Some(group) if !group.has_glyph_width => {
+                        for index in group.text {
+                            self.tokens[index].issue = Some(
+                                "visible text inside `%{ %}`: zsh counts it as zero width \
+                                 (declare its width with `%G`)"
+                                    .to_string(),
+                            );
+                        }
+                    }
+                    Some(_) => {}
+                    None => self.issue("`%}` without `%{`"),
+                }
+            }
+            'G' => {
+                let cells = number.unwrap_or(1).max(0) as usize;
+                self.push(
+                    start,
+                    format!("declare {} cell(s) of width", cells),
+                    text(""),
+                );
+                match &mut self.group {
+                    Some(group) => group.has_glyph_width = true,
+                    None => self.issue("`%G` outside `%{ %}` has no effect"),
+                }
+            }
+            '%' => self.push(start, "literal `%`", text("%")),
+            ')' => self.push(start, "literal `)`", text(")")),
+            'n' => self.push(start, "username", Some(ctx.username.clone())),
+            'm' => self.push(
+                start,
+                "hostname up to the first dot",
+                Some(ctx.hostname.clone()),
+            ),
+            'M' => self.push(start, "full hostname", None),
+            '~' => self.push(
+                start,
+                "working directory, `~` for home",
+                Some(path_components(&ctx.cwd_tilde(), number)),
+            ),
+            '/' | 'd' => self.push(
+                start,
+                "working directory",
+                Some(path_components(&ctx.cwd_full(), number)),
+            ),
+            'c' | '.' | 'C' => {
+                let path = if c == 'C' {
+                    ctx.cwd_full()
+                } else {
+                    ctx.cwd_tilde()
+                };
+                self.push(
+                    start,
+                    "trailing component(s) of the working directory",
+                    Some(path_components(&path, Some(number.unwrap_or(1)))),
+                );
+            }
+            '#' => self.push(
+                start,
+                "`#` when privileged, `%` otherwise",
+                text(if ctx.privileged { "#" } else { "%" }),
+            ),
+            '?' => self.push(
+                start,
+                "exit status of the last command",
+                Some(ctx.status.unwrap_or(0).to_string()),
+            ),
+            'j' => self.push(start, "number of jobs", Some(ctx.jobs.to_string())),
+            'L' => self.push(
+                start,
+                "shell level ($SHLVL)",
+                ctx.var("SHLVL").map(str::to_string),
+            ),
+            'l' | 'y' => self.push(start, "terminal line (tty)", None),
+            'h' | '!' => self.push(start, "history event number", None),
+            'i' | 'I' => self.push(start, "line number", None),
+            'N' | 'x' => self.push(start, "script or function name", None),
+            'e' => self.push(start, "evaluation depth", None),
+            'D' if self.chars.get(self.pos) == Some(&'{') => {
+                self.braced();
+                self.push(start, "date/time (strftime format)", None);
+            }
+            'D' | 'T' | 't' | '@' | '*' | 'w' | 'W' => self.push(start, "date/time", None),
+            'B' => self.push(start, "start bold", text("")),
+            'b' => self.push(start, "stop bold", text("")),
+            'U' => self.push(start, "start underline", text("")),
+            'u' => self.push(start, "stop underline", text("")),
+            'S' => self.push(start, "start standout", text("")),
+            's' => self.push(start, "stop standout", text("")),
+            'E' => self.push(start, "clear to end of line", text("")),
+            'F' | 'K' => {
+                let color = self
+                    .braced()
+                    .or_else(|| number.map(|n| n.to_string()))
+                    .unwrap_or_else(|| "default".to_string());
+                let kind = if c == 'F' { "foreground" } else { "background" };
+                self.push(start, format!("{} {}", kind, color), text(""));
+            }
+            'f' => self.push(start, "reset foreground", text("")),
+            'k' => self.push(start, "reset background", text("")),
+            '(' => self.conditional(start, number),
+            other => {
+                self.push(start, "unknown escape", None);
+                self.issue(format!("`%{}` is not a prompt escape", other));
+            }
+        }
+    }
+
+    /// Parses `%(x.true.false)` and explains the branch selected by the context.
+    fn conditional(&mut self, start: usize, number: Option<i32>) {
+        let number = self.number().or(number);
+        let condition = self.chars.get(self.pos).copied();
+        let separator = self.chars.get(self.pos + 1).copied();
+        let (Some(condition), Some(separator)) = (condition, separator) else {
+            self.pos = self.chars.len();
+            self.push(start, "conditional", None);
+            self.issue("unterminated `%(`");
+            return;
+        };
+        self.pos += 2;
+        let Some(when_true) = self.until(&[separator]) else {
+            self.push(start, "conditional", None);
+            self.issue("unterminated `%(`");
+            return;
+        };
+        let Some(when_false) = self.until(&[')']) else {
+            self.push(start, "conditional", None);
+            self.issue("unterminated `%(`");
+            return;
+        };
+
+        let ctx = self.ctx;
+        let n = number.unwrap_or(0);
+        let (description, result) = match condition {
+            '?' => (
+                format!("exit status is {}", n),
+                Some(ctx.status.unwrap_or(0) == n),
+            ),
+            '!' => ("privileged".to_string(), Some(ctx.privileged)),
+            '#' => (format!("effective uid is {}", n), None),
+            'j' => (
+                format!("at least {} job(s)", number.unwrap_or(1)),
+                Some(ctx.jobs as i32 >= number.unwrap_or(1)),
+            ),
+            'L' => (
+                format!("$SHLVL is at least {}", n),
+                ctx.var("SHLVL")
+                    .and_then(|level| level.parse::<i32>().ok())
+                    .map(|level| level >= n),
+            ),
+            'c' | '.' | '~' | '/' | 'C' => (
+                format!("working directory has at least {} component(s)", n),
+                None,
+            ),
+            other => (format!("condition `{}`", other), None),
+        };
+        let meaning = format!(
+            "if {} then `{}` else `{}`",
+            description,
+            printable(&when_true),
+            printable(&when_false)
+        );
+
+        let branch = result.map(|result| if result { when_true } else { when_false });
+        let inner = branch.map(|branch| explain(&branch, ctx));
+        let value = inner.as_ref().and_then(|inner| {
+            inner
+                .tokens
+                .iter()
+                .map(|token| token.value.clone())
+                .collect::<Option<String>>()
+        });
+        self.push(start, meaning, value);
+        if let Some(inner) = inner {
+            let last = self.tokens.last_mut().expect("token was just pushed");
+            last.width = inner.width();
+            last.issue = inner
+                .issues()
+                .next()
+                .and_then(|token| token.issue.clone())
+                .map(|issue| format!("in the selected branch: {}", issue));
+        }
+    }
+}
+
+/// Like `printable`, but quotes text with leading or trailing whitespace so it
+/// stays visible in the table.
+fn cell(s: &str) -> String {
+    let s = printable(s);
+    if s.trim() == s {
+        s
+    } else {
+        format!("\"{}\"", s)
+    }
+}
+
+/// Pads `s` to `width` terminal cells.
+fn pad(s: &str, width: usize) -> String {
+    format!("{}{}", s, " ".repeat(width.saturating_sub(s.width())))
+}
+
+impl fmt::Display for Explanation {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let rows: Vec<[String; 4]> = self
+            .tokens
+            .iter()
+            .map(|token| {
+                let mark = if token.issue.is_some() { "! " } else { "  " };
+                [
+                    format!("{}{}", mark, cell(&token.source)),
+                    token.meaning.clone(),
+                    token
+                        .value
+                        .as_deref()
+                        .map(cell)
+                        .unwrap_or_else(|| "?".to_string()),
+                    token
+                        .width
+                        .map(|w| w.to_string())
+                        .unwrap_or_else(|| "?".to_string()),
+                ]
+            })
+            .collect();
+        let header = ["  ESCAPE", "MEANING", "VALUE", "WIDTH"].map(str::to_string);
+        let mut widths = header.clone().map(|h| h.width());
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.width());
+            }
+        }
+        for row in std::iter::once(&header).chain(&rows) {
+            let line = format!(
+                "{}  {}  {}  {}",
+                pad(&row[0], widths[0]),
+                pad(&row[1], widths[1]),
+                pad(&row[2], widths[2]),
+                row[3]
+            );
+            writeln!(f, "{}", line.trim_end())?;
+        }
+
+        match self.width() {
+            Some(width) => writeln!(f, "\ntotal width: {}", width)?,
+            None => writeln!(f, "\ntotal width: unknown outside zsh")?,
+        }
+        for token in self.issues() {
+            if let Some(issue) = &token.issue {
+                writeln!(
+                    f,
+                    "column {}: `{}`: {}",
+                    token.column,
+                    printable(&token.source),
+                    issue
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn ctx() -> PromptContext {
+        PromptContext {
+            username: "me".to_string(),
+            hostname: "box".to_string(),
+            cwd: PathBuf::from("/home/me/src/zsh-seq"),
+            home: Some(PathBuf::from("/home/me")),
+            status: Some(1),
+            ..Default::default()
+        }
+    }
+
+    fn sources(explanation: &Explanation) -> Vec<&str> {
+        explanation
+            .tokens
+            .iter()
+            .map(|token| token.source.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn test_explain_tokens() {
+        let explanation = explain("%{%F{red}%}%n@%m%{%f%} %2~ %#", &ctx());
+        assert_eq!(
+            sources(&explanation),
+            [
+                "%{", "%F{red}", "%}", "%n", "@", "%m", "%{", "%f", "%}", " ", "%2~", " ", "%#"
+            ]
+        );
+        assert_eq!(explanation.tokens[1].meaning, "foreground red");
+        assert_eq!(explanation.tokens[10].value.as_deref(), Some("src/zsh-seq"));
+        assert_eq!(explanation.width(), Some(20));
+        assert_eq!(explanation.issues().count(), 0);
+    }
+
+    #[test]
+    fn test_conditional() {
+        let explanation = explain("%(?.ok.%F{red}fail %?%f)!", &ctx());
+        assert_eq!(sources(&explanation), ["%(?.ok.%F{red}fail %?%f)", "!"]);
+        let token = &explanation.tokens[0];
+        assert_eq!(
+            token.meaning,
+            "if exit status is 0 then `ok` else `%F{red}fail %?%f`"
+        );
+        assert_eq!(token.value.as_deref(), Some("fail 1"));
+        assert_eq!(token.width, Some(6));
+
+        let unknown = explain("%(#.root.user)", &ctx());
+        assert_eq!(unknown.width(), None);
+        for prompt in ["%(?.a", "%(?.a%", "%(?.a.b%"] {
+            let explanation = explain(prompt, &ctx());
+            assert_eq!(explanation.tokens.len(), 1);
+            assert_eq!(
+                explanation.tokens[0].issue.as_deref(),
+                Some("unterminated `%(`")
+            );
+        }
+    }
+
+    #[test]
+    fn test_zero_width_problems() {
+        let explanation = explain("\x1b[1m%n%{\x1b[0m%}", &ctx());
+        let issues: Vec<usize> = explanation.issues().map(|token| token.column).collect();
+        assert_eq!(issues, [1]);
+        assert_eq!(explanation.tokens[0].meaning, "ANSI style 1");
+
+        let hidden = explain("%{❯%}", &ctx());
+        assert!(
+            hidden.tokens[1]
+                .issue
+                .as_ref()
+                .unwrap()
+                .contains("zero width")
+        );
+        assert_eq!(explain("%{%G❯%}", &ctx()).issues().count(), 0);
+
+        assert!(explain("%{%B", &ctx()).tokens[0].issue.is_some());
+        assert!(explain("%}", &ctx()).tokens[0].issue.is_some());
+        assert!(explain("%G", &ctx()).tokens[0].issue.is_some());
+        assert!(explain("%Z", &ctx()).tokens[0].issue.is_some());
+        for prompt in ["\x1b[31", "\x1b]0;title", "\x1b"] {
+            let explanation = explain(prompt, &ctx());
+            assert_eq!(explanation.tokens[0].source, prompt);
+        }
+        assert_eq!(
+            explain("\x1b[31", &ctx()).tokens[0].issue.as_deref(),
+            Some("unterminated escape code")
+        );
+    }
+
+    #[test]
+    fn test_path_components() {
+        assert_eq!(path_components("~/src/zsh-seq", Some(1)), "zsh-seq");
+        assert_eq!(path_components("/usr/local/bin", Some(-2)), "/usr/local");
+        assert_eq!(path_components("/usr", Some(3)), "/usr");
+        assert_eq!(path_components("/usr/local", None), "/usr/local");
+    }
+
+    #[test]
+    fn test_table() {
+        let table = explain("%B\x1b[31mhi", &ctx()).to_string();
+        assert!(table.starts_with("  ESCAPE"));
+        assert!(table.contains("! \\e[31m  ANSI style 31"));
+        assert!(table.contains("total width: 2\n"));
+        let space = explain(" ", &ctx()).to_string();
+        let row = space.lines().nth(1).unwrap();
+        assert!(row.starts_with("  \" \""));
+        assert_eq!(row.matches("\" \"").count(), 2);
+        assert!(table.contains("column 3: `\\e[31m`: escape code outside `%{ %}`"));
+    }
+}
@@ -0,0 +1,108 @@
+use unicode_width::UnicodeWidthStr;
+
+/// Which glyphs icons are drawn with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IconSet {
+    /// Private-use-area glyphs from a Nerd Font.
+    NerdFont,
+    /// Standard Unicode symbols and emoji that most fonts cover.
+    #[default]
+    Unicode,
+    /// Short ASCII tags such as `py` or `k8s`.
+    Ascii,
+    /// Icons are left out.
+    None,
+}
+
+impl IconSet {
+    /// Space separating an icon from its text; empty when icons are left out.
+    pub fn space(&self) -> &'static str {
+        match self {
+            IconSet::None => "",
+            _ => " ",
+        }
+    }
+}
+
+/// An icon that resolves to a glyph depending on the selected `IconSet`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Icon {
+    GitBranch,
+    Lock,
+    Failure,
+    Jobs,
+    Python,
+    Conda,
+    Nix,
+    Direnv,
+    Rust,
+    Node,
+    Go,
+    Kubernetes,
+    Aws,
+    Docker,
+}
+
+impl Icon {
+    /// Returns the glyph for the given set.
+    pub fn glyph(&self, set: IconSet) -> &'static str {
+        let (nerd_font, unicode, ascii) = match self {
+            Icon::GitBranch => ("\u{e0a0}", "⎇", "git:"),
+            Icon::Lock => ("\u{f023}", "🔒", "ro"),
+            Icon::Failure => ("\u{f00d}", "✘", "x"),
+            Icon::Jobs => ("\u{f013}", "✦", "&"),
+            Icon::Python => ("\u{e73c}", "🐍", "py"),
+            // nf-dev-anaconda
+            Icon::Conda => ("\u{e715}", "🅒", "conda"),
+            Icon::Nix => ("\u{f313}", "❄", "nix"),
+            Icon::Direnv => ("\u{f07c}", "📂", "env"),
+            Icon::Rust => ("\u{e7a8}", "🦀", "rs"),
+            Icon::Node => ("\u{e718}", "⬢", "node"),
+            Icon::Go => ("\u{e627}", "🐹", "go"),
+            Icon::Kubernetes => ("\u{f10fe}", "☸", "k8s"),
+            Icon::Aws => ("\u{e7ad}", "☁", "aws"),
+            Icon::Docker => ("\u{f308}", "🐳", "docker"),
+        };
+        match set {
+            IconSet::NerdFont => nerd_font,
+            IconSet::Unicode => unicode,
+            IconSet::Ascii => ascii,
+            IconSet::None => "",
+        }
+    }
+
+    /// Number of terminal cells the glyph occupies.
+    ///
+    /// Nerd Font glyphs live in the private use area, whose width Unicode leaves
+    /// undefined; the patched fonts draw them in a single cell.
+    pub fn width(&self, set: IconSet) -> usize {
+        let glyph = self.glyph(set);
+        match set {
+            IconSet::NerdFont => glyph.chars().count(),
+            _ => UnicodeWidthStr::width(glyph),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_glyph_per_set() {
+        assert_eq!(Icon::Python.glyph(IconSet::NerdFont), "\u{e73c}");
+        assert_eq!(Icon::Python.glyph(IconSet::Unicode), "🐍");
+        assert_eq!(Icon::Python.glyph(IconSet::Ascii), "py");
+        assert_eq!(Icon::Python.glyph(IconSet::None), "");
+        assert_eq!(Icon::Conda.glyph(IconSet::NerdFont), "\u{e715}");
+    }
+
+    #[test]
+    fn test_width() {
+        assert_eq!(Icon::Kubernetes.width(IconSet::NerdFont), 1);
+        assert_eq!(Icon::Python.width(IconSet::Unicode), 2);
+        assert_eq!(Icon::Kubernetes.width(IconSet::Unicode), 1);
+        assert_eq!(Icon::Docker.width(IconSet::Ascii), 6);
+        assert_eq!(Icon::Docker.width(IconSet::None), 0);
+    }
+}
@@ -0,0 +1,113 @@
+//! Shell integration printed by `zsh_seq_cli init zsh`.
+
+use std::path::Path;
+
+use crate::segments::duration;
+
+/// Quotes a string for zsh using single quotes.
+fn quote(s: &str) -> String {
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
+
+/// Generates the snippet to `eval` in `.zshrc`.
+///
+/// Before each prompt it calls `<binary> render --both` once with the exit
+/// status of the last command, the job count, the terminal width and the
+/// named directories (`hash -d`), and splits the output into
+/// `PROMPT`/`RPROMPT`. The duration is measured by
+/// `duration::zsh_hooks`, whose variable `render` reads from the environment.
+/// A `zle-keymap-select` widget renders again with `--keymap` when the vi
+/// mode changes, after calling the `zle-keymap-select` widget defined before
+/// it, if any.
+/// The rendered text is assigned directly, so `prompt_subst` is not needed and
+/// is left alone. When the user has enabled it anyway, the prompts go through
+/// a parameter so that `$` and backticks in the output (branch names, paths)
+/// are never expanded.
+///
+/// Every definition is overwritten and `add-zsh-hook` skips hooks that are
+/// already registered, so evaluating the snippet twice is harmless.
+pub fn zsh_init_script(binary: &Path) -> String {
+    format!(
+        r#"# zsh_seq: eval "$({bin} init zsh)"
+{hooks}zmodload zsh/parameter
+typeset -g _zsh_seq_left= _zsh_seq_right=
+typeset -ga _zsh_seq_args=()
+_zsh_seq_render() {{
+    # 左右を一度に描画し、NUL で分ける
+    local output="$({bin} render --both "${{_zsh_seq_args[@]}}" "$@")"
+    _zsh_seq_left=${{output%%$'\0'*}}
+    _zsh_seq_right=${{output#*$'\0'}}
+    if [[ -o prompt_subst ]]; then
+        PROMPT='${{_zsh_seq_left}}'
+        RPROMPT='${{_zsh_seq_right}}'
+    else
+        PROMPT=$_zsh_seq_left
+        RPROMPT=$_zsh_seq_right
+    fi
+}}
+_zsh_seq_precmd() {{
+    # local 自体が $pipestatus を上書きするので、両方を同じ文で退避する
+    local last_status=$? last_pipestatus=(${{pipestatus[@]}})
+    _zsh_seq_args=(--status $last_status --pipestatus "${{last_pipestatus[*]}}"
+        --jobs ${{#jobstates}})
+    if [[ -n $COLUMNS ]]; then
+        _zsh_seq_args+=(--columns $COLUMNS)
+    fi
+    # hash -d で登録された名前付きディレクトリ
+    local name
+    for name in ${{(k)nameddirs}}; do
+        _zsh_seq_args+=(--named-dir "$name=${{nameddirs[$name]}}")
+    done
+    _zsh_seq_render
+}}
+# 既存の zle-keymap-select (プラグインなど) を退避して先に呼ぶ
+if [[ -n ${{widgets[zle-keymap-select]}} &&
+      ${{widgets[zle-keymap-select]}} != user:_zsh_seq_keymap_select ]]; then
+    zle -A zle-keymap-select _zsh_seq_orig_keymap_select
+fi
+_zsh_seq_keymap_select() {{
+    if [[ -n ${{widgets[_zsh_seq_orig_keymap_select]}} ]]; then
+        zle _zsh_seq_orig_keymap_select -- "$@"
+    fi
+    _zsh_seq_render --keymap "$KEYMAP"
+    zle reset-prompt
+}}
+add-zsh-hook precmd _zsh_seq_precmd
+zle -N zle-keymap-select _zsh_seq_keymap_select
+"#,
+        bin = quote(&binary.display().to_string()),
+        hooks = duration::zsh_hooks()
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote("/usr/bin/zsh_seq_cli"), "'/usr/bin/zsh_seq_cli'");
+        assert_eq!(quote("/it's/bin"), r"'/it'\''s/bin'");
+    }
+
+    #[test]
+    fn test_zsh_init_script() {
+        let script = zsh_init_script(Path::new("/opt/my tools/zsh_seq_cli"));
+        assert!(script.contains(
+            "\"$('/opt/my tools/zsh_seq_cli' render --both \"${_zsh_seq_args[@]}\" \"$@\")\""
+        ));
+        // プロンプトごとに一度だけ起動する
+        assert_eq!(script.matches(" render ").count(), 1);
+        // 実行時間は duration::zsh_hooks に任せる
+        assert!(script.contains(&duration::zsh_hooks()));
+        assert!(!script.contains("--duration-ms"));
+        assert!(script.contains("_zsh_seq_args+=(--columns $COLUMNS)"));
+        assert!(script.contains("_zsh_seq_args+=(--named-dir \"$name=${nameddirs[$name]}\")"));
+        assert!(script.contains("    local last_status=$? last_pipestatus=(${pipestatus[@]})\n"));
+        assert!(script.contains("zle -N zle-keymap-select _zsh_seq_keymap_select\n"));
+        assert!(script.contains("zle -A zle-keymap-select _zsh_seq_orig_keymap_select\n"));
+        assert!(script.contains("add-zsh-hook precmd _zsh_seq_precmd\n"));
+        // prompt_subst は有効化しない
+        assert!(!script.contains("setopt"));
+    }
+}
@@ -1,9 +1,38 @@
+mod macros;
+
+#[doc(hidden)]
+pub use macros::__private;
+
+pub mod accessibility;
+pub mod backends;
 pub mod builder;
 pub mod colors;
+pub mod config;
+pub mod context;
+pub mod explain;
+pub mod icons;
+pub mod init;
+pub mod markup;
+pub mod options;
+pub mod powerline;
+pub mod preview;
+pub mod segments;
 pub mod sequences;
+pub mod starship;
 pub mod traits;
+pub mod validation;
 
+pub use accessibility::ColorBlindness;
+pub use backends::Shell;
 pub use builder::ZshPromptBuilder;
 pub use colors::NamedColor;
-pub use sequences::ZshSequence;
+pub use config::{PromptConfig, PromptSet};
+pub use context::PromptContext;
+pub use icons::{Icon, IconSet};
+pub use options::BuildOptions;
+pub use powerline::{Powerline, PowerlineSegment};
+pub use segments::{Segment, SegmentRegistry};
+pub use sequences::{ZshCondition, ZshSequence};
+pub use starship::StarshipFormat;
 pub use traits::ColoredZshPrompt;
+pub use validation::{ValidationIssue, ValidationOptions};
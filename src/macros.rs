@@ -0,0 +1,499 @@
+/// Builds a zsh prompt string at compile time.
+///
+/// Expands to a `&'static str`, so it can initialize a `const`. Styles wrap a
+/// braced body and are closed automatically, so they are always balanced;
+/// nested colors restore the enclosing color when they end.
+///
+/// - `"text"`: escaped like `ZshPromptBuilder::str` (`%` becomes `%%`, other
+///   non-ASCII characters are wrapped in `%{%G..%}`)
+/// - `user`, `host`, `cwd`, `cwd_full`, `priv`, `status`, `newline`
+/// - `bold { .. }`, `underline { .. }`, `standout { .. }`
+/// - `fg(COLOR) { .. }`, `bg(COLOR) { .. }` where `COLOR` is a name such as
+///   `red` or `light_blue`, a decimal 256-color code such as `208`, or
+///   `rgb(255, 136, 0)`
+///
+/// The macro recurses once per style and per run of up to four plain items,
+/// so prompts well beyond a hundred items fit in the default recursion limit.
+/// Extremely long prompts may need a higher `#![recursion_limit]`.
+///
+/// ```
+/// const PROMPT: &str = zsh_seq::zsh_prompt!(
+///     bold { fg(red) { user } } "@" host " " fg(blue) { cwd } " " priv " "
+/// );
+/// assert_eq!(
+///     PROMPT,
+///     "%{%B%}%{%F{red}%}%n%{%f%}%{%b%}@%m %{%F{blue}%}%~%{%f%} %# "
+/// );
+/// ```
+///
+/// Unknown names and out-of-range codes fail to compile at the offending token:
+///
+/// ```compile_fail
+/// const PROMPT: &str = zsh_seq::zsh_prompt!(fg(purple) { user });
+/// ```
+///
+/// ```compile_fail
+/// const PROMPT: &str = zsh_seq::zsh_prompt!(fg(256) { user });
+/// ```
+///
+/// ```compile_fail
+/// const PROMPT: &str = zsh_seq::zsh_prompt!(fg(0xff) { user });
+/// ```
+#[macro_export]
+macro_rules! zsh_prompt {
+    ($($tokens:tt)*) => {
+        $crate::__zsh_prompt!([] [] [_ _ _ _ _] $($tokens)*)
+    };
+}
+
+/// Token muncher behind `zsh_prompt!`.
+///
+/// State: `[output] [color checks] [fg bg bold underline standout]`, where `_`
+/// means unset. Ending a style is done by a marker pushed after its body.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_prompt {
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt]) => {{
+        const _: () = { $($crate::__zsh_check! $check;)* };
+        const PIECES: &[(bool, &str)] = &[$($out),*];
+        const BYTES: [u8; $crate::__private::prompt_len(PIECES)] =
+            $crate::__private::prompt_bytes(PIECES);
+        match ::core::str::from_utf8(&BYTES) {
+            Ok(prompt) => prompt,
+            Err(_) => panic!("zsh_prompt! produced invalid UTF-8"),
+        }
+    }};
+
+    // スタイルの終了マーカー
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] @fg_end $prev:tt $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* (false, $crate::__zsh_fg!($prev)),] [$($check)*] [$prev $bg $b $u $s] $($rest)*)
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] @bg_end $prev:tt $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* (false, $crate::__zsh_bg!($prev)),] [$($check)*] [$fg $prev $b $u $s] $($rest)*)
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] @b_end $prev:tt $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* (false, $crate::__zsh_unless!($prev, "%{%b%}")),] [$($check)*] [$fg $bg $prev $u $s] $($rest)*)
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] @u_end $prev:tt $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* (false, $crate::__zsh_unless!($prev, "%{%u%}")),] [$($check)*] [$fg $bg $b $prev $s] $($rest)*)
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] @s_end $prev:tt $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* (false, $crate::__zsh_unless!($prev, "%{%s%}")),] [$($check)*] [$fg $bg $b $u $prev] $($rest)*)
+    };
+
+    // スタイル
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] fg($($color:tt)+) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* (false, $crate::__zsh_fg!(($($color)+))),] [$($check)* ($($color)+)] [($($color)+) $bg $b $u $s]
+            $($body)* @fg_end $fg $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] bg($($color:tt)+) { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* (false, $crate::__zsh_bg!(($($color)+))),] [$($check)* ($($color)+)] [$fg ($($color)+) $b $u $s]
+            $($body)* @bg_end $bg $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] bold { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* (false, $crate::__zsh_unless!($b, "%{%B%}")),] [$($check)*] [$fg $bg 1 $u $s]
+            $($body)* @b_end $b $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] underline { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* (false, $crate::__zsh_unless!($u, "%{%U%}")),] [$($check)*] [$fg $bg $b 1 $s]
+            $($body)* @u_end $u $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] [$fg:tt $bg:tt $b:tt $u:tt $s:tt] standout { $($body:tt)* } $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* (false, $crate::__zsh_unless!($s, "%{%S%}")),] [$($check)*] [$fg $bg $b $u 1]
+            $($body)* @s_end $s $($rest)*
+        )
+    };
+
+    // 値とテキストは4つずつまとめて再帰の深さを抑える。最後がリテラルなら
+    // 途中の識別子の後にスタイルの本体が続くことはない
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:ident $b:ident $c:ident $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:ident $b:ident $c:literal $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:ident $b:literal $c:ident $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:ident $b:literal $c:literal $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:literal $b:ident $c:ident $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:literal $b:ident $c:literal $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:literal $b:literal $c:ident $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $a:literal $b:literal $c:literal $d:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!(
+            [$($out,)* $crate::__zsh_item!($a), $crate::__zsh_item!($b), $crate::__zsh_item!($c), $crate::__zsh_item!($d),]
+            [$($check)*] $state $($rest)*
+        )
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $item:ident $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* $crate::__zsh_item!($item),] [$($check)*] $state $($rest)*)
+    };
+    ([$($out:expr,)*] [$($check:tt)*] $state:tt $item:literal $($rest:tt)*) => {
+        $crate::__zsh_prompt!([$($out,)* $crate::__zsh_item!($item),] [$($check)*] $state $($rest)*)
+    };
+}
+
+/// A plain item as a `(escape, text)` piece.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_item {
+    ($text:literal) => {
+        (true, $text)
+    };
+    ($value:ident) => {
+        (false, $crate::__zsh_value!($value))
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_value {
+    (user) => {
+        "%n"
+    };
+    (host) => {
+        "%m"
+    };
+    (cwd) => {
+        "%~"
+    };
+    (cwd_full) => {
+        "%/"
+    };
+    (priv) => {
+        "%#"
+    };
+    (status) => {
+        "%?"
+    };
+    (newline) => {
+        "\n"
+    };
+}
+
+/// Emits `$seq` unless the attribute was already on.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_unless {
+    (_, $seq:literal) => {
+        $seq
+    };
+    (1, $seq:literal) => {
+        ""
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_fg {
+    (_) => {
+        "%{%f%}"
+    };
+    ((rgb($r:literal, $g:literal, $b:literal))) => {
+        concat!("%{\x1b[38;2;", $r, ";", $g, ";", $b, "m%}")
+    };
+    (($($color:tt)+)) => {
+        concat!("%{%F{", $crate::__zsh_color!($($color)+), "}%}")
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_bg {
+    (_) => {
+        "%{%k%}"
+    };
+    ((rgb($r:literal, $g:literal, $b:literal))) => {
+        concat!("%{\x1b[48;2;", $r, ";", $g, ";", $b, "m%}")
+    };
+    (($($color:tt)+)) => {
+        concat!("%{%K{", $crate::__zsh_color!($($color)+), "}%}")
+    };
+}
+
+/// Color names, matching `NamedColor::to_zsh_string`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_color {
+    (black) => {
+        "black"
+    };
+    (red) => {
+        "red"
+    };
+    (green) => {
+        "green"
+    };
+    (yellow) => {
+        "yellow"
+    };
+    (blue) => {
+        "blue"
+    };
+    (magenta) => {
+        "magenta"
+    };
+    (cyan) => {
+        "cyan"
+    };
+    (white) => {
+        "white"
+    };
+    (light_black) => {
+        "240"
+    };
+    (light_red) => {
+        "lightred"
+    };
+    (light_green) => {
+        "lightgreen"
+    };
+    (light_yellow) => {
+        "lightyellow"
+    };
+    (light_blue) => {
+        "lightblue"
+    };
+    (light_magenta) => {
+        "lightmagenta"
+    };
+    (light_cyan) => {
+        "lightcyan"
+    };
+    (light_white) => {
+        "white"
+    };
+    ($code:literal) => {
+        stringify!($code)
+    };
+}
+
+/// Rejects color codes that do not fit in a `u8` or are not plain decimals.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __zsh_check {
+    (rgb($r:literal, $g:literal, $b:literal)) => {
+        let _: [u8; 3] = [$r, $g, $b];
+        $crate::__zsh_check!($r);
+        $crate::__zsh_check!($g);
+        $crate::__zsh_check!($b);
+    };
+    ($code:literal) => {
+        let _: u8 = $code;
+        assert!(
+            $crate::__private::is_decimal(stringify!($code)),
+            "color codes must be written as plain decimals"
+        );
+    };
+    ($name:ident) => {};
+}
+
+/// Const helpers used by the expansion of `zsh_prompt!`.
+#[doc(hidden)]
+pub mod __private {
+    /// Length of a UTF-8 sequence starting with `byte`.
+    const fn char_len(byte: u8) -> usize {
+        match byte {
+            0xf0.. => 4,
+            0xe0.. => 3,
+            0xc0.. => 2,
+            _ => 1,
+        }
+    }
+
+    /// Length of the joined pieces, escaping those marked `true`.
+    pub const fn prompt_len(pieces: &[(bool, &str)]) -> usize {
+        let mut len = 0;
+        let mut i = 0;
+        while i < pieces.len() {
+            let (escape, text) = pieces[i];
+            let bytes = text.as_bytes();
+            let mut j = 0;
+            while j < bytes.len() {
+                let n = char_len(bytes[j]);
+                len += match (escape, bytes[j]) {
+                    (true, b'%') => 2,
+                    // %{%G..%} で囲む
+                    (true, 0x80..) => n + 6,
+                    _ => n,
+                };
+                j += n;
+            }
+            i += 1;
+        }
+        len
+    }
+
+    /// Joins the pieces, escaping text like `ZshSequence::Literal`.
+    pub const fn prompt_bytes<const N: usize>(pieces: &[(bool, &str)]) -> [u8; N] {
+        let mut out = [0u8; N];
+        let mut len = 0;
+        let mut i = 0;
+        while i < pieces.len() {
+            let (escape, text) = pieces[i];
+            let bytes = text.as_bytes();
+            let mut j = 0;
+            while j < bytes.len() {
+                let n = char_len(bytes[j]);
+                let wide = escape && bytes[j] >= 0x80;
+                if wide {
+                    out[len] = b'%';
+                    out[len + 1] = b'{';
+                    out[len + 2] = b'%';
+                    out[len + 3] = b'G';
+                    len += 4;
+                }
+                if escape && bytes[j] == b'%' {
+                    out[len] = b'%';
+                    len += 1;
+                }
+                let mut k = 0;
+                while k < n {
+                    out[len] = bytes[j + k];
+                    len += 1;
+                    k += 1;
+                }
+                if wide {
+                    out[len] = b'%';
+                    out[len + 1] = b'}';
+                    len += 2;
+                }
+                j += n;
+            }
+            i += 1;
+        }
+        out
+    }
+
+    /// True for a non-empty string of ASCII digits.
+    pub const fn is_decimal(s: &str) -> bool {
+        let bytes = s.as_bytes();
+        let mut i = 0;
+        while i < bytes.len() {
+            if !bytes[i].is_ascii_digit() {
+                return false;
+            }
+            i += 1;
+        }
+        !bytes.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    const PROMPT: &str = crate::zsh_prompt!(
+        bold { fg(red) { user } } "@" host " " fg(blue) { cwd } " " priv " "
+    );
+
+    #[test]
+    fn test_zsh_prompt() {
+        assert_eq!(
+            PROMPT,
+            "%{%B%}%{%F{red}%}%n%{%f%}%{%b%}@%m %{%F{blue}%}%~%{%f%} %# "
+        );
+        assert_eq!(crate::zsh_prompt!(), "");
+    }
+
+    #[test]
+    fn test_nested_styles_restore_outer() {
+        assert_eq!(
+            crate::zsh_prompt!(fg(blue) { "a" fg(208) { "b" } "c" }),
+            "%{%F{blue}%}a%{%F{208}%}b%{%F{blue}%}c%{%f%}"
+        );
+        assert_eq!(
+            crate::zsh_prompt!(bold { "a" bold { "b" } "c" } bg(rgb(0, 0, 255)) { status }),
+            "%{%B%}abc%{%b%}%{\x1b[48;2;0;0;255m%}%?%{%k%}"
+        );
+    }
+
+    #[test]
+    fn test_text_is_escaped() {
+        use crate::builder::ZshPromptBuilder;
+        assert_eq!(crate::zsh_prompt!("100%"), "100%%");
+        assert_eq!(
+            crate::zsh_prompt!(status "% ❯ " fg(red) { "✘" }),
+            ZshPromptBuilder::new()
+                .exit_status()
+                .str("% ❯ ")
+                .color(crate::colors::NamedColor::Red)
+                .str("✘")
+                .end_color()
+                .build()
+        );
+    }
+
+    #[test]
+    fn test_long_prompt_fits_recursion_limit() {
+        // 既定の recursion_limit (128) を超える項目数
+        const LONG: &str = crate::zsh_prompt!(
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            user "@" host " " cwd " " priv " " user "@" host " " cwd " " priv " "
+            bold { fg(red) { user } } newline priv
+        );
+        assert_eq!(LONG.matches("%n@%m %~ %# ").count(), 20);
+        assert!(LONG.ends_with("%{%B%}%{%F{red}%}%n%{%f%}%{%b%}\n%#"));
+    }
+
+    #[test]
+    fn test_matches_builder() {
+        use crate::builder::ZshPromptBuilder;
+        use crate::colors::NamedColor;
+        let built = ZshPromptBuilder::new()
+            .color(NamedColor::LightBlue)
+            .current_dir_tilde()
+            .end_color()
+            .newline()
+            .privileged_indicator()
+            .build();
+        assert_eq!(
+            crate::zsh_prompt!(fg(light_blue) { cwd } newline priv),
+            built
+        );
+    }
+}
@@ -1,44 +1,273 @@
-use zsh_seq::{ColoredZshPrompt, NamedColor, ZshPromptBuilder};
-
-use std::process::Command;
-
-/// Zshのプロンプトエスケープを解釈した結果を、標準出力にそのまま表示するテスト用関数
-fn test_output_in_zsh(prompt: &str) {
-    let output = Command::new("zsh")
-        .arg("-c")
-        // print -P はプロンプトシーケンスを解釈して出力するコマンド
-        .arg(format!("print -P '{}'", prompt))
-        .output()
-        .expect("failed to execute zsh");
-
-    if output.status.success() {
-        // Zshが解釈した結果（カラーコード等を含む）をターミナルに表示
-        println!("{}", String::from_utf8_lossy(&output.stdout));
-    } else {
-        eprintln!("Error: {}", String::from_utf8_lossy(&output.stderr));
-    }
-}
-fn main() {
-    // 1. Builderで作成した複雑なプロンプト
-    let prompt = ZshPromptBuilder::new()
-        .bold()
-        .color(NamedColor::FullColor((0, 255, 0)))
-        .username()
-        .str("@")
-        .hostname_short()
-        .end_bold()
-        .str(" ")
-        .current_dir_tilde()
-        .str(" ")
-        .privileged_indicator()
-        .build();
-
-    println!("Raw string: {}", prompt);
-    print!("Zsh rendered: ");
-    test_output_in_zsh(&prompt);
-
-    // 2. トレイトを使った簡便な装飾
-    let warning = "Critical Error".red().bold().on_yellow();
-    print!("Trait rendered: ");
-    test_output_in_zsh(&warning);
+use std::env;
+use std::io::{self, IsTerminal};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::time::Duration;
+
+use zsh_seq::config::ConfigError;
+use zsh_seq::explain::explain;
+use zsh_seq::init::zsh_init_script;
+use zsh_seq::preview::{Panel, Scenario, layout};
+use zsh_seq::{
+    BuildOptions, PromptConfig, PromptContext, PromptSet, SegmentRegistry, StarshipFormat,
+    ZshPromptBuilder,
+};
+
+const USAGE: &str = "usage: zsh_seq_cli <command> [options]
+
+commands:
+  init zsh      print the snippet to eval in ~/.zshrc
+  explain <prompt>
+                annotate each escape of a prompt string and report
+                escape codes zsh would count as visible
+  preview       render the prompt side by side in simulated situations
+                (root, failed command, git repository, long path, narrow
+                terminal) without zsh
+                  --config <file>       preview a configuration file
+                  --markup <text>       preview a markup prompt
+                  --starship <format>   preview a starship format string
+                  --plain               print without colors (the default
+                                        when stdout is not a terminal)
+  render        print the prompt for the given context
+                  --right               render RPROMPT instead of PROMPT
+                  --both                render PROMPT and RPROMPT, separated
+                                        by a NUL byte
+                  --status <n>          exit status of the last command
+                  --pipestatus '<n>...' statuses of the last pipeline
+                  --duration-ms <n>     how long the last command ran
+                  --jobs <n>            number of background jobs
+                  --columns <n>         width of the terminal
+                  --keymap <name>       active zle keymap (e.g. vicmd)
+                  --named-dir <name>=<path>
+                                        named directory shown as ~name
+                                        (repeatable)
+
+The configuration is read from $ZSH_SEQ_CONFIG or
+~/.config/zsh_seq/config.toml, falling back to the builtin prompt.";
+
+/// Options of the `render` subcommand.
+#[derive(Debug, Default)]
+struct RenderArgs {
+    right: bool,
+    both: bool,
+    status: Option<i32>,
+    pipestatus: Vec<i32>,
+    duration: Option<Duration>,
+    jobs: usize,
+    columns: Option<usize>,
+    keymap: Option<String>,
+    named_dirs: Vec<(String, PathBuf)>,
+}
+
+fn parse_number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T, String> {
+    value
+        .trim()
+        .parse()
+        .map_err(|_| format!("{}: invalid number `{}`", flag, value))
+}
+
+fn parse_render_args(args: &[String]) -> Result<RenderArgs, String> {
+    let mut parsed = RenderArgs::default();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let flag = flag.as_str();
+        if flag == "--right" {
+            parsed.right = true;
+            continue;
+        }
+        if flag == "--both" {
+            parsed.both = true;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{}: missing value", flag))?;
+        match flag {
+            "--status" => parsed.status = Some(parse_number(flag, value)?),
+            "--pipestatus" => {
+                parsed.pipestatus = value
+                    .split_whitespace()
+                    .map(|status| parse_number(flag, status))
+                    .collect::<Result<_, _>>()?;
+            }
+            "--duration-ms" => {
+                parsed.duration = Some(Duration::from_millis(parse_number(flag, value)?));
+            }
+            "--jobs" => parsed.jobs = parse_number(flag, value)?,
+            "--columns" => parsed.columns = Some(parse_number(flag, value)?),
+            "--keymap" => parsed.keymap = Some(value.clone()).filter(|k| !k.is_empty()),
+            "--named-dir" => {
+                let (name, path) = value
+                    .split_once('=')
+                    .filter(|(name, path)| !name.is_empty() && !path.is_empty())
+                    .ok_or_else(|| format!("{}: expected `name=path`, got `{}`", flag, value))?;
+                parsed
+                    .named_dirs
+                    .push((name.to_string(), PathBuf::from(path)));
+            }
+            _ => return Err(format!("unknown option `{}`\n\n{}", flag, USAGE)),
+        }
+    }
+    Ok(parsed)
+}
+
+fn init(args: &[String]) -> Result<(), String> {
+    match args {
+        [shell] if shell == "zsh" => {
+            let binary = env::current_exe().unwrap_or_else(|_| PathBuf::from("zsh_seq_cli"));
+            print!("{}", zsh_init_script(&binary));
+            Ok(())
+        }
+        [shell] => Err(format!(
+            "unsupported shell `{}` (only zsh is supported)",
+            shell
+        )),
+        _ => Err(USAGE.to_string()),
+    }
+}
+
+fn render(args: &[String]) -> Result<(), String> {
+    let args = parse_render_args(args)?;
+    let mut ctx = PromptContext::current();
+    ctx.status = args.status;
+    ctx.pipestatus = args.pipestatus;
+    ctx.jobs = args.jobs;
+    ctx.keymap = args.keymap;
+    ctx.named_dirs = args.named_dirs;
+    if args.duration.is_some() {
+        ctx.duration = args.duration;
+    }
+    if args.columns.is_some() {
+        ctx.columns = args.columns;
+    }
+
+    // 片側だけなら、もう一方のセグメント (git など) は実行しない
+    let registry = SegmentRegistry::with_builtins();
+    let compile = |config: &PromptConfig| -> Result<String, ConfigError> {
+        if args.both {
+            let prompts = config.compile(&registry, &ctx)?;
+            Ok(format!(
+                "{}\0{}",
+                prompts.left.build(),
+                prompts.right.unwrap_or_default().build()
+            ))
+        } else if args.right {
+            Ok(config
+                .compile_right(&registry, &ctx)?
+                .unwrap_or_default()
+                .build())
+        } else {
+            Ok(config.compile_left(&registry, &ctx)?.build())
+        }
+    };
+    // 設定が壊れていてもプロンプトは出し、エラーは stderr で知らせる
+    let (prompt, config_error) = match PromptConfig::load_user(&ctx).and_then(|c| compile(&c)) {
+        Ok(prompt) => (prompt, None),
+        Err(err) => {
+            let message = match (&err.location, PromptConfig::user_path(&ctx)) {
+                (Some(_), Some(path)) => format!("{}: {}", path.display(), err),
+                _ => err.to_string(),
+            };
+            let prompt = compile(&PromptConfig::builtin()).map_err(|err| err.to_string())?;
+            (prompt, Some(message))
+        }
+    };
+    print!("{}", prompt);
+    match config_error {
+        Some(message) => Err(message),
+        None => Ok(()),
+    }
+}
+
+/// What `preview` renders.
+enum PreviewSource {
+    Config(PromptConfig),
+    Prompt(ZshPromptBuilder),
+    Starship(StarshipFormat),
+}
+
+impl PreviewSource {
+    fn compile(&self, scenario: &Scenario) -> Result<PromptSet, String> {
+        let registry = scenario.registry();
+        let left = match self {
+            PreviewSource::Config(config) => {
+                return config
+                    .compile(&registry, &scenario.ctx)
+                    .map_err(|err| err.to_string());
+            }
+            PreviewSource::Prompt(prompt) => prompt.clone(),
+            PreviewSource::Starship(format) => {
+                ZshPromptBuilder::new().chain(format.to_sequences(&registry, &scenario.ctx))
+            }
+        };
+        Ok(PromptSet { left, right: None })
+    }
+}
+
+fn preview(args: &[String]) -> Result<(), String> {
+    let ctx = PromptContext::current();
+    let mut source = None;
+    // TERM=dumb や NO_COLOR、端末以外への出力では色を付けない
+    let mut color = BuildOptions::detect().colors && io::stdout().is_terminal();
+    let mut iter = args.iter();
+    while let Some(flag) = iter.next() {
+        let flag = flag.as_str();
+        if flag == "--plain" {
+            color = false;
+            continue;
+        }
+        let value = iter
+            .next()
+            .ok_or_else(|| format!("{}: missing value", flag))?;
+        source = Some(match flag {
+            "--config" => PreviewSource::Config(
+                PromptConfig::load(Path::new(value))
+                    .map_err(|err| format!("{}: {}", value, err))?,
+            ),
+            "--markup" => PreviewSource::Prompt(
+                ZshPromptBuilder::markup(value).map_err(|err| err.to_string())?,
+            ),
+            "--starship" => PreviewSource::Starship(
+                StarshipFormat::parse(value).map_err(|err| err.to_string())?,
+            ),
+            _ => return Err(format!("unknown option `{}`\n\n{}", flag, USAGE)),
+        });
+    }
+    let source = match source {
+        Some(source) => source,
+        None => {
+            PreviewSource::Config(PromptConfig::load_user(&ctx).map_err(|err| err.to_string())?)
+        }
+    };
+
+    let panels = Scenario::builtin()
+        .iter()
+        .map(|scenario| Ok(Panel::render(scenario, &source.compile(scenario)?, color)))
+        .collect::<Result<Vec<_>, String>>()?;
+    print!("{}", layout(&panels, ctx.columns.unwrap_or(120)));
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let result = match args.first().map(String::as_str) {
+        Some("init") => init(&args[1..]),
+        Some("render") => render(&args[1..]),
+        Some("preview") => preview(&args[1..]),
+        Some("explain") => match &args[1..] {
+            [prompt] => {
+                print!("{}", explain(prompt, &PromptContext::current()));
+                Ok(())
+            }
+            _ => Err(USAGE.to_string()),
+        },
+        _ => Err(USAGE.to_string()),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(message) => {
+            eprintln!("{}", message);
+            ExitCode::from(2)
+        }
+    }
 }
@@ -0,0 +1,286 @@
+//! A small markup language for styled prompt text.
+//!
+//! ```text
+//! <b><fg=red>%n</fg></b> <fg=#88f>{cwd}</fg>
+//! ```
+//!
+//! Tags: `<b>`, `<u>`, `<s>` (standout), `<fg=COLOR>`, `<bg=COLOR>`, each closed
+//! by `</name>`. Closing a color restores the enclosing one. Dynamic values are
+//! written as placeholders (`{user}`, `{host}`, `{cwd}`, `{cwd_full}`,
+//! `{priv}`, `{status}`, `{newline}`) or as the zsh escapes `%n`, `%m`, `%~`,
+//! `%/`, `%#`, `%?` and `%%`. A backslash makes the next character literal.
+
+use std::fmt;
+
+use crate::colors::NamedColor;
+use crate::sequences::ZshSequence;
+
+/// Error for malformed markup. `column` counts characters from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarkupError {
+    pub message: String,
+    pub column: usize,
+}
+
+impl fmt::Display for MarkupError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for MarkupError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Tag {
+    Bold,
+    Underline,
+    Standout,
+    Fg(NamedColor),
+    Bg(NamedColor),
+}
+
+impl Tag {
+    fn name(&self) -> &'static str {
+        match self {
+            Tag::Bold => "b",
+            Tag::Underline => "u",
+            Tag::Standout => "s",
+            Tag::Fg(_) => "fg",
+            Tag::Bg(_) => "bg",
+        }
+    }
+}
+
+fn placeholder(name: &str) -> Option<ZshSequence> {
+    let seq = match name {
+        "user" | "username" => ZshSequence::Username,
+        "host" | "hostname" => ZshSequence::HostnameShort,
+        "cwd" => ZshSequence::CurrentDirectoryTilde,
+        "cwd_full" => ZshSequence::CurrentDirectoryFull,
+        "priv" => ZshSequence::PrivilegedIndicator,
+        "status" => ZshSequence::ExitStatus,
+        "newline" => ZshSequence::Newline,
+        _ => return None,
+    };
+    Some(seq)
+}
+
+fn escape(c: char) -> Option<ZshSequence> {
+    let seq = match c {
+        'n' => ZshSequence::Username,
+        'm' => ZshSequence::HostnameShort,
+        '~' => ZshSequence::CurrentDirectoryTilde,
+        '/' => ZshSequence::CurrentDirectoryFull,
+        '#' => ZshSequence::PrivilegedIndicator,
+        '?' => ZshSequence::ExitStatus,
+        '%' => ZshSequence::Percent,
+        _ => return None,
+    };
+    Some(seq)
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+    sequences: Vec<ZshSequence>,
+    text: String,
+    /// Open tags with the column they were opened at.
+    open: Vec<(Tag, usize)>,
+}
+
+impl Parser {
+    fn error(&self, column: usize, message: impl Into<String>) -> MarkupError {
+        MarkupError {
+            message: message.into(),
+            column: column + 1,
+        }
+    }
+
+    fn push(&mut self, seq: ZshSequence) {
+        if !self.text.is_empty() {
+            self.sequences
+                .push(ZshSequence::Literal(std::mem::take(&mut self.text)));
+        }
+        self.sequences.push(seq);
+    }
+
+    /// Reads up to (and consumes) `close`, returning the contents.
+    fn until(&mut self, close: char, start: usize) -> Result<String, MarkupError> {
+        let begin = self.pos;
+        while self.chars.get(self.pos).is_some_and(|c| *c != close) {
+            self.pos += 1;
+        }
+        if self.pos >= self.chars.len() {
+            return Err(self.error(start, format!("missing closing `{}`", close)));
+        }
+        let content = self.chars[begin..self.pos].iter().collect();
+        self.pos += 1;
+        Ok(content)
+    }
+
+    fn parse(mut self) -> Result<Vec<ZshSequence>, MarkupError> {
+        while let Some(&c) = self.chars.get(self.pos) {
+            let start = self.pos;
+            self.pos += 1;
+            match c {
+                '\\' => match self.chars.get(self.pos) {
+                    Some(&next) => {
+                        self.text.push(next);
+                        self.pos += 1;
+                    }
+                    None => return Err(self.error(start, "trailing backslash")),
+                },
+                '%' => {
+                    let Some(&next) = self.chars.get(self.pos) else {
+                        return Err(
+                            self.error(start, "`%` at end of input (write `%%` for a literal `%`)")
+                        );
+                    };
+                    let seq = escape(next).ok_or_else(|| {
+                        self.error(start, format!("unsupported escape `%{}`", next))
+                    })?;
+                    self.pos += 1;
+                    self.push(seq);
+                }
+                '{' => {
+                    let name = self.until('}', start)?;
+                    let seq = placeholder(name.trim()).ok_or_else(|| {
+                        self.error(start, format!("unknown placeholder `{{{}}}`", name))
+                    })?;
+                    self.push(seq);
+                }
+                '<' => {
+                    let tag = self.until('>', start)?;
+                    match tag.strip_prefix('/') {
+                        Some(name) => self.close(name.trim(), start)?,
+                        None => self.open(tag.trim(), start)?,
+                    }
+                }
+                c => self.text.push(c),
+            }
+        }
+        if let Some((tag, column)) = self.open.last() {
+            return Err(self.error(*column, format!("unclosed `<{}>`", tag.name())));
+        }
+        if !self.text.is_empty() {
+            let text = std::mem::take(&mut self.text);
+            self.sequences.push(ZshSequence::Literal(text));
+        }
+        Ok(self.sequences)
+    }
+
+    fn open(&mut self, tag: &str, start: usize) -> Result<(), MarkupError> {
+        let color = |value: &str| {
+            value
+                .trim()
+                .parse::<NamedColor>()
+                .map_err(|err| self.error(start, err.to_string()))
+        };
+        let (tag, seq) = match tag.split_once('=') {
+            Some(("fg", value)) => {
+                let color = color(value)?;
+                (Tag::Fg(color), ZshSequence::ForegroundColor(color))
+            }
+            Some(("bg", value)) => {
+                let color = color(value)?;
+                (Tag::Bg(color), ZshSequence::BackgroundColor(color))
+            }
+            None if tag == "b" => (Tag::Bold, ZshSequence::BoldStart),
+            None if tag == "u" => (Tag::Underline, ZshSequence::UnderlineStart),
+            None if tag == "s" => (Tag::Standout, ZshSequence::StandoutStart),
+            _ => return Err(self.error(start, format!("unknown tag `<{}>`", tag))),
+        };
+        self.open.push((tag, start));
+        self.push(seq);
+        Ok(())
+    }
+
+    fn close(&mut self, name: &str, start: usize) -> Result<(), MarkupError> {
+        let Some((tag, _)) = self.open.pop() else {
+            return Err(self.error(start, format!("`</{}>` without an opening tag", name)));
+        };
+        if tag.name() != name {
+            return Err(self.error(
+                start,
+                format!("expected `</{}>`, found `</{}>`", tag.name(), name),
+            ));
+        }
+        // 外側に同じ種類のタグがあればその状態に戻す
+        let outer = self.open.iter().rev().map(|(tag, _)| *tag);
+        let seq = match tag {
+            Tag::Fg(_) => match outer.clone().find_map(|t| match t {
+                Tag::Fg(color) => Some(color),
+                _ => None,
+            }) {
+                Some(color) => ZshSequence::ForegroundColor(color),
+                None => ZshSequence::ForegroundColorEnd,
+            },
+            Tag::Bg(_) => match outer.clone().find_map(|t| match t {
+                Tag::Bg(color) => Some(color),
+                _ => None,
+            }) {
+                Some(color) => ZshSequence::BackgroundColor(color),
+                None => ZshSequence::BackgroundColorEnd,
+            },
+            _ if outer.clone().any(|t| t == tag) => return Ok(()),
+            Tag::Bold => ZshSequence::BoldEnd,
+            Tag::Underline => ZshSequence::UnderlineEnd,
+            Tag::Standout => ZshSequence::StandoutEnd,
+        };
+        self.push(seq);
+        Ok(())
+    }
+}
+
+/// Parses markup into sequences.
+pub fn parse(markup: &str) -> Result<Vec<ZshSequence>, MarkupError> {
+    Parser {
+        chars: markup.chars().collect(),
+        pos: 0,
+        sequences: Vec::new(),
+        text: String::new(),
+        open: Vec::new(),
+    }
+    .parse()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::builder::ZshPromptBuilder;
+
+    fn build(markup: &str) -> String {
+        ZshPromptBuilder::markup(markup).unwrap().build()
+    }
+
+    #[test]
+    fn test_markup() {
+        assert_eq!(
+            build("<b><fg=red>%n</fg></b> <fg=#88f>{cwd}</fg>"),
+            "%{%B%}%{%F{red}%}%n%{%f%}%{%b%} %{\x1b[38;2;136;136;255m%}%~%{%f%}"
+        );
+        assert_eq!(build(r"100%% \<b\> \{x}"), "100%% <b> {x}");
+    }
+
+    #[test]
+    fn test_nesting_restores_outer_style() {
+        assert_eq!(
+            build("<fg=blue>a<fg=red>b</fg>c</fg>"),
+            "%{%F{blue}%}a%{%F{red}%}b%{%F{blue}%}c%{%f%}"
+        );
+        assert_eq!(build("<b>a<b>b</b>c</b>"), "%{%B%}a%{%B%}bc%{%b%}");
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = ZshPromptBuilder::markup("<b><fg=red>x</b></fg>").unwrap_err();
+        assert_eq!(err.to_string(), "column 13: expected `</fg>`, found `</b>`");
+        let err = ZshPromptBuilder::markup("ab<u>c").unwrap_err();
+        assert_eq!(err.to_string(), "column 3: unclosed `<u>`");
+        assert_eq!(ZshPromptBuilder::markup("x</b>").unwrap_err().column, 2);
+        assert!(ZshPromptBuilder::markup("<fg=mauve>").is_err());
+        assert!(ZshPromptBuilder::markup("<i>").is_err());
+        assert!(ZshPromptBuilder::markup("{pwd}").is_err());
+        assert!(ZshPromptBuilder::markup("%x").is_err());
+        assert!(ZshPromptBuilder::markup("<b").is_err());
+    }
+}
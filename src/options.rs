@@ -0,0 +1,132 @@
+use std::env;
+
+use crate::sequences::ZshSequence;
+
+/// Controls which kinds of escape sequences `ZshPromptBuilder::build_with` emits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BuildOptions {
+    /// Emit foreground/background colors.
+    pub colors: bool,
+    /// Emit bold, underline and standout.
+    pub attributes: bool,
+}
+
+impl Default for BuildOptions {
+    fn default() -> Self {
+        Self {
+            colors: true,
+            attributes: true,
+        }
+    }
+}
+
+impl BuildOptions {
+    /// Strips colors but keeps bold/underline/standout.
+    pub fn monochrome() -> Self {
+        Self {
+            colors: false,
+            attributes: true,
+        }
+    }
+
+    /// Strips every style sequence, leaving only text.
+    pub fn plain() -> Self {
+        Self {
+            colors: false,
+            attributes: false,
+        }
+    }
+
+    /// Detects the options from the current process environment.
+    pub fn detect() -> Self {
+        Self::detect_from(|key| env::var(key).ok())
+    }
+
+    /// Detects the options from an arbitrary variable lookup.
+    ///
+    /// `TERM=dumb` disables all styling, a non-empty `NO_COLOR`
+    /// (see <https://no-color.org>) disables colors only.
+    pub fn detect_from(lookup: impl Fn(&str) -> Option<String>) -> Self {
+        if lookup("TERM").as_deref() == Some("dumb") {
+            return Self::plain();
+        }
+        if lookup("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Self::monochrome();
+        }
+        Self::default()
+    }
+
+    /// Keeps only the sequences allowed by these options, including inside conditionals.
+    pub fn filter(&self, sequences: &[ZshSequence]) -> Vec<ZshSequence> {
+        sequences
+            .iter()
+            .filter(|seq| self.allows(seq))
+            .map(|seq| match seq {
+                ZshSequence::Conditional {
+                    condition,
+                    when_true,
+                    when_false,
+                } => ZshSequence::Conditional {
+                    condition: *condition,
+                    when_true: self.filter(when_true),
+                    when_false: self.filter(when_false),
+                },
+                _ => seq.clone(),
+            })
+            .collect()
+    }
+
+    /// Returns whether `seq` should be emitted under these options.
+    pub fn allows(&self, seq: &ZshSequence) -> bool {
+        if seq.is_color() {
+            self.colors
+        } else if seq.is_style() {
+            // ResetStyles も属性の一種として扱う
+            self.attributes
+        } else {
+            true
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lookup<'a>(vars: &'a [(&'a str, &'a str)]) -> impl Fn(&str) -> Option<String> + 'a {
+        move |key| {
+            vars.iter()
+                .find(|(k, _)| *k == key)
+                .map(|(_, v)| v.to_string())
+        }
+    }
+
+    #[test]
+    fn test_detect_defaults_to_color() {
+        assert_eq!(
+            BuildOptions::detect_from(lookup(&[("TERM", "xterm-256color")])),
+            BuildOptions::default()
+        );
+    }
+
+    #[test]
+    fn test_detect_no_color() {
+        assert_eq!(
+            BuildOptions::detect_from(lookup(&[("NO_COLOR", "1")])),
+            BuildOptions::monochrome()
+        );
+        // 空の NO_COLOR は無視する
+        assert_eq!(
+            BuildOptions::detect_from(lookup(&[("NO_COLOR", "")])),
+            BuildOptions::default()
+        );
+    }
+
+    #[test]
+    fn test_detect_dumb_terminal() {
+        assert_eq!(
+            BuildOptions::detect_from(lookup(&[("TERM", "dumb"), ("NO_COLOR", "1")])),
+            BuildOptions::plain()
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+
+/// Glyph set used for powerline separators.
+///
+/// All sets except `Ascii` need a Nerd Font / powerline-patched font.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PowerlineGlyphs {
+    #[default]
+    Sharp,
+    Round,
+    Slant,
+    Flame,
+    Ascii,
+}
+
+/// Which side of the terminal the powerline is drawn on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Orientation {
+    /// Arrows point right; for `PROMPT`.
+    #[default]
+    Left,
+    /// Arrows point left; for `RPROMPT`.
+    Right,
+}
+
+impl PowerlineGlyphs {
+    /// Returns `(solid, thin)` separators. The thin one divides neighbors with the same background.
+    pub fn separators(&self, orientation: Orientation) -> (&'static str, &'static str) {
+        match (self, orientation) {
+            (PowerlineGlyphs::Sharp, Orientation::Left) => ("\u{e0b0}", "\u{e0b1}"),
+            (PowerlineGlyphs::Sharp, Orientation::Right) => ("\u{e0b2}", "\u{e0b3}"),
+            (PowerlineGlyphs::Round, Orientation::Left) => ("\u{e0b4}", "\u{e0b5}"),
+            (PowerlineGlyphs::Round, Orientation::Right) => ("\u{e0b6}", "\u{e0b7}"),
+            (PowerlineGlyphs::Slant, Orientation::Left) => ("\u{e0bc}", "\u{e0bd}"),
+            (PowerlineGlyphs::Slant, Orientation::Right) => ("\u{e0ba}", "\u{e0bb}"),
+            (PowerlineGlyphs::Flame, Orientation::Left) => ("\u{e0c0}", "\u{e0c1}"),
+            (PowerlineGlyphs::Flame, Orientation::Right) => ("\u{e0c2}", "\u{e0c3}"),
+            (PowerlineGlyphs::Ascii, Orientation::Left) => (">", "|"),
+            (PowerlineGlyphs::Ascii, Orientation::Right) => ("<", "|"),
+        }
+    }
+}
+
+/// One block of a powerline prompt.
+///
+/// `content` should not change the background itself; foreground changes are fine
+/// as long as they are closed with another `color` rather than `end_color`.
+#[derive(Debug, Clone)]
+pub struct PowerlineSegment {
+    pub bg: NamedColor,
+    pub fg: NamedColor,
+    pub content: ZshPromptBuilder,
+}
+
+impl PowerlineSegment {
+    pub fn new(bg: NamedColor, fg: NamedColor, content: ZshPromptBuilder) -> Self {
+        Self { bg, fg, content }
+    }
+}
+
+/// Lays out segments as a powerline, computing the color transitions between them.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Powerline {
+    pub glyphs: PowerlineGlyphs,
+    pub orientation: Orientation,
+    /// Put a space on both sides of each segment's content.
+    pub padding: bool,
+}
+
+impl Default for Powerline {
+    fn default() -> Self {
+        Self {
+            glyphs: PowerlineGlyphs::default(),
+            orientation: Orientation::default(),
+            padding: true,
+        }
+    }
+}
+
+impl Powerline {
+    fn body(&self, builder: ZshPromptBuilder, segment: &PowerlineSegment) -> ZshPromptBuilder {
+        let pad = if self.padding { " " } else { "" };
+        builder
+            .color_bg(segment.bg)
+            .color(segment.fg)
+            .str(pad)
+            .connect(segment.content.clone())
+            .str(pad)
+    }
+
+    pub fn render(&self, segments: &[PowerlineSegment]) -> ZshPromptBuilder {
+        let (solid, thin) = self.glyphs.separators(self.orientation);
+        let mut builder = ZshPromptBuilder::new();
+        if segments.is_empty() {
+            return builder;
+        }
+
+        match self.orientation {
+            Orientation::Left => {
+                for (i, segment) in segments.iter().enumerate() {
+                    builder = self.body(builder, segment);
+                    match segments.get(i + 1) {
+                        // 同じ背景色が続く場合は細い区切りを前景色で描く
+                        Some(next) if next.bg == segment.bg => {
+                            builder = builder.color(segment.fg).str(thin);
+                        }
+                        // 矢印の前景色を現在の背景色、背景色を次の背景色にする
+                        Some(next) => {
+                            builder = builder.color(segment.bg).color_bg(next.bg).str(solid);
+                        }
+                        None => {
+                            builder = builder
+                                .end_color_bg()
+                                .color(segment.bg)
+                                .str(solid)
+                                .end_color();
+                        }
+                    }
+                }
+            }
+            Orientation::Right => {
+                let mut previous: Option<&PowerlineSegment> = None;
+                for segment in segments {
+                    builder = match previous {
+                        Some(prev) if prev.bg == segment.bg => builder.color(prev.fg).str(thin),
+                        _ => builder.color(segment.bg).str(solid),
+                    };
+                    builder = self.body(builder, segment);
+                    previous = Some(segment);
+                }
+                builder = builder.end_color_bg().end_color();
+            }
+        }
+        builder
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segments() -> Vec<PowerlineSegment> {
+        vec![
+            PowerlineSegment::new(
+                NamedColor::Blue,
+                NamedColor::White,
+                ZshPromptBuilder::new().username(),
+            ),
+            PowerlineSegment::new(
+                NamedColor::Blue,
+                NamedColor::Black,
+                ZshPromptBuilder::new().hostname_short(),
+            ),
+            PowerlineSegment::new(
+                NamedColor::Green,
+                NamedColor::Black,
+                ZshPromptBuilder::new().current_dir_tilde(),
+            ),
+        ]
+    }
+
+    #[test]
+    fn test_left_transitions() {
+        let powerline = Powerline {
+            glyphs: PowerlineGlyphs::Ascii,
+            ..Default::default()
+        };
+        assert_eq!(
+            powerline.render(&segments()).build(),
+            "%{%K{blue}%}%{%F{white}%} %n %{%F{white}%}|\
+             %{%K{blue}%}%{%F{black}%} %m %{%F{blue}%}%{%K{green}%}>\
+             %{%K{green}%}%{%F{black}%} %~ %{%k%}%{%F{green}%}>%{%f%}"
+        );
+    }
+
+    #[test]
+    fn test_right_transitions() {
+        let powerline = Powerline {
+            glyphs: PowerlineGlyphs::Ascii,
+            orientation: Orientation::Right,
+            padding: false,
+        };
+        assert_eq!(
+            powerline.render(&segments()).build(),
+            "%{%F{blue}%}<%{%K{blue}%}%{%F{white}%}%n\
+             %{%F{white}%}|%{%K{blue}%}%{%F{black}%}%m\
+             %{%F{green}%}<%{%K{green}%}%{%F{black}%}%~%{%k%}%{%f%}"
+        );
+    }
+
+    #[test]
+    fn test_glyph_sets() {
+        assert_eq!(
+            PowerlineGlyphs::Round.separators(Orientation::Left),
+            ("\u{e0b4}", "\u{e0b5}")
+        );
+        let prompt = Powerline::default().render(&segments()[2..]).build();
+        assert!(prompt.ends_with("%{%k%}%{%F{green}%}%{%G\u{e0b0}%}%{%f%}"));
+        assert_eq!(Powerline::default().render(&[]).build(), "");
+    }
+}
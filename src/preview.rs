@@ -0,0 +1,338 @@
+//! Renders a prompt under simulated contexts, for `zsh_seq_cli preview`.
+//!
+//! Every scenario is rendered offline into a panel as wide as its simulated
+//! terminal, with `RPROMPT` placed (or hidden) the way zsh would, and the
+//! panels are laid out side by side.
+
+use std::path::PathBuf;
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::backends::styled::{Style, styled_lines};
+use crate::builder::ZshPromptBuilder;
+use crate::config::PromptSet;
+use crate::context::PromptContext;
+use crate::segments::git::{GitSegment, GitStatus};
+use crate::segments::{Segment, SegmentRegistry};
+
+/// A simulated situation to render the prompt in.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Scenario {
+    pub name: String,
+    pub ctx: PromptContext,
+    /// What the `git` segment reports; `None` outside a repository.
+    pub git: Option<GitStatus>,
+}
+
+impl Scenario {
+    /// A regular user in a project directory after a successful command.
+    pub fn base() -> Self {
+        Self {
+            name: "default".to_string(),
+            ctx: PromptContext {
+                username: "user".to_string(),
+                hostname: "host".to_string(),
+                cwd: PathBuf::from("/home/user/projects/app"),
+                home: Some(PathBuf::from("/home/user")),
+                status: Some(0),
+                pipestatus: vec![0],
+                columns: Some(60),
+                ..Default::default()
+            },
+            git: None,
+        }
+    }
+
+    /// Default, root user, failed command, git repository, long path and
+    /// narrow terminal.
+    pub fn builtin() -> Vec<Self> {
+        let base = Self::base();
+        let with = |name: &str, f: &dyn Fn(&mut Self)| {
+            let mut scenario = base.clone();
+            scenario.name = name.to_string();
+            f(&mut scenario);
+            scenario
+        };
+        let long_path = PathBuf::from("/home/user/work/clients/acme/backend/services/billing/src");
+        vec![
+            base.clone(),
+            with("root", &|s| {
+                s.ctx.username = "root".to_string();
+                s.ctx.privileged = true;
+                s.ctx.cwd = PathBuf::from("/etc/nginx");
+            }),
+            with("failed command", &|s| {
+                s.ctx.status = Some(1);
+                s.ctx.pipestatus = vec![0, 1];
+            }),
+            with("git repository", &|s| {
+                s.git = Some(GitStatus {
+                    branch: Some("main".to_string()),
+                    commit: Some("1a2b3c4".to_string()),
+                    upstream: Some("origin/main".to_string()),
+                    ahead: 1,
+                    staged: 2,
+                    unstaged: 1,
+                    untracked: 3,
+                    ..Default::default()
+                });
+            }),
+            with("long path", &|s| s.ctx.cwd = long_path.clone()),
+            with("narrow terminal", &|s| {
+                s.ctx.cwd = long_path.clone();
+                s.ctx.columns = Some(32);
+            }),
+        ]
+    }
+
+    /// Terminal width of the scenario, 80 if unset.
+    pub fn columns(&self) -> usize {
+        self.ctx.columns.unwrap_or(80)
+    }
+
+    /// The builtin segments, with `git` reporting the simulated status instead
+    /// of reading the working directory.
+    pub fn registry(&self) -> SegmentRegistry {
+        let mut registry = SegmentRegistry::with_builtins();
+        registry.register(SimulatedGit {
+            segment: GitSegment::default(),
+            status: self.git.clone(),
+        });
+        registry
+    }
+}
+
+struct SimulatedGit {
+    segment: GitSegment,
+    status: Option<GitStatus>,
+}
+
+impl Segment for SimulatedGit {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    fn render(&self, _ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        self.status
+            .as_ref()
+            .map(|status| self.segment.render_status(status))
+    }
+}
+
+/// A rendered scenario: its title and the terminal lines, with their widths.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Panel {
+    pub title: String,
+    pub lines: Vec<(String, usize)>,
+    /// Simulated terminal width; lines may be wider.
+    pub columns: usize,
+}
+
+impl Panel {
+    /// Renders the prompts as they would appear in the scenario's terminal.
+    ///
+    /// Like zsh, `RPROMPT` goes at the end of the last line, one column from
+    /// the edge, and is left out when it would touch the left prompt.
+    pub fn render(scenario: &Scenario, prompts: &PromptSet, color: bool) -> Self {
+        let ctx = &scenario.ctx;
+        let columns = scenario.columns();
+        let mut lines: Vec<(String, usize)> = styled_lines(prompts.left.sequences(), ctx)
+            .iter()
+            .map(|runs| paint(runs, color))
+            .collect();
+        let right = prompts
+            .right
+            .as_ref()
+            .and_then(|right| styled_lines(right.sequences(), ctx).into_iter().next())
+            .map(|runs| paint(&runs, color))
+            .filter(|(_, width)| *width > 0);
+        if let (Some((right, right_width)), Some((last, width))) = (right, lines.last_mut())
+            && *width + right_width + 2 <= columns
+        {
+            let gap = columns - 1 - *width - right_width;
+            last.push_str(&" ".repeat(gap));
+            last.push_str(&right);
+            *width += gap + right_width;
+        }
+        Self {
+            title: format!("{} ({} columns)", scenario.name, columns),
+            lines,
+            columns,
+        }
+    }
+
+    /// Width of the panel: the terminal, or a longer line that would wrap.
+    pub fn width(&self) -> usize {
+        self.lines
+            .iter()
+            .map(|(_, width)| *width)
+            .chain([self.columns, self.title.width()])
+            .max()
+            .unwrap_or(0)
+    }
+}
+
+/// Joins styled runs into one line, returning it and its display width.
+fn paint(runs: &[(Style, String)], color: bool) -> (String, usize) {
+    let mut line = String::new();
+    let mut width = 0;
+    for (style, text) in runs {
+        width += text.width();
+        if !color || *style == Style::default() {
+            line.push_str(text);
+            continue;
+        }
+        let mut params = Vec::new();
+        if style.bold {
+            params.push("1".to_string());
+        }
+        if style.underline {
+            params.push("4".to_string());
+        }
+        if style.standout {
+            params.push("7".to_string());
+        }
+        params.extend(style.fg.map(|fg| fg.to_ansi_sgr(false)));
+        params.extend(style.bg.map(|bg| bg.to_ansi_sgr(true)));
+        // 各ランの後でリセットし、隣のパネルに色を持ち越さない
+        line.push_str(&format!("\x1b[{}m{}\x1b[0m", params.join(";"), text));
+    }
+    (line, width)
+}
+
+/// Lays the panels out side by side, starting a new row whenever the next
+/// panel would not fit in `max_width`.
+pub fn layout(panels: &[Panel], max_width: usize) -> String {
+    const SEPARATOR: &str = " │ ";
+    let mut rows: Vec<Vec<&Panel>> = Vec::new();
+    let mut row_width = 0;
+    for panel in panels {
+        let width = panel.width();
+        match rows.last_mut() {
+            Some(row) if row_width + SEPARATOR.width() + width <= max_width => {
+                row.push(panel);
+                row_width += SEPARATOR.width() + width;
+            }
+            _ => {
+                rows.push(vec![panel]);
+                row_width = width;
+            }
+        }
+    }
+
+    let mut output = String::new();
+    for (i, row) in rows.iter().enumerate() {
+        if i > 0 {
+            output.push('\n');
+        }
+        let height = row.iter().map(|p| p.lines.len()).max().unwrap_or(0);
+        let cells = |f: &dyn Fn(&Panel) -> (String, usize)| {
+            let line = row
+                .iter()
+                .map(|panel| {
+                    let (text, width) = f(panel);
+                    format!(
+                        "{}{}",
+                        text,
+                        " ".repeat(panel.width().saturating_sub(width))
+                    )
+                })
+                .collect::<Vec<_>>()
+                .join(SEPARATOR);
+            format!("{}\n", line.trim_end())
+        };
+        output.push_str(&cells(&|p| (p.title.clone(), p.title.width())));
+        output.push_str(&cells(&|p| ("─".repeat(p.width()), p.width())));
+        for line in 0..height {
+            output.push_str(&cells(&|p| p.lines.get(line).cloned().unwrap_or_default()));
+        }
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PromptConfig;
+
+    fn plain_panel(scenario: &Scenario, source: &str) -> Panel {
+        let config = PromptConfig::parse(source).unwrap();
+        let prompts = config.compile(&scenario.registry(), &scenario.ctx).unwrap();
+        Panel::render(scenario, &prompts, false)
+    }
+
+    const CONFIG: &str = r#"
+[[line]]
+[[line.item]]
+value = "directory"
+
+[[line.item]]
+segment = "git"
+
+[[line]]
+[[line.item]]
+value = "privileged"
+
+[right]
+[[right.item]]
+segment = "status"
+"#;
+
+    fn texts(panel: &Panel) -> Vec<&str> {
+        panel.lines.iter().map(|(line, _)| line.as_str()).collect()
+    }
+
+    #[test]
+    fn test_scenarios() {
+        let scenarios = Scenario::builtin();
+        let panels: Vec<Panel> = scenarios.iter().map(|s| plain_panel(s, CONFIG)).collect();
+        assert_eq!(texts(&panels[0]), ["~/projects/app", "%"]);
+        assert_eq!(texts(&panels[1]), ["/etc/nginx", "#"]);
+        assert_eq!(
+            panels[2].lines[1].0.trim_end(),
+            format!("%{}✘ 0|1", " ".repeat(53))
+        );
+        assert_eq!(panels[2].lines[1].1, 59);
+        assert!(panels[3].lines[0].0.starts_with("~/projects/app main"));
+        assert_eq!(panels[5].title, "narrow terminal (32 columns)");
+    }
+
+    #[test]
+    fn test_right_prompt_hidden_when_it_does_not_fit() {
+        let mut scenario = Scenario::base();
+        scenario.ctx.status = Some(1);
+        scenario.ctx.columns = Some(5);
+        let panel = plain_panel(&scenario, CONFIG);
+        assert_eq!(texts(&panel), ["~/projects/app", "%"]);
+        assert_eq!(panel.width(), 19);
+    }
+
+    #[test]
+    fn test_paint() {
+        let style = Style {
+            bold: true,
+            fg: Some(crate::colors::NamedColor::Red),
+            ..Default::default()
+        };
+        let runs = vec![
+            (style, "ab".to_string()),
+            (Style::default(), "❯".to_string()),
+        ];
+        assert_eq!(paint(&runs, true), ("\x1b[1;31mab\x1b[0m❯".to_string(), 3));
+        assert_eq!(paint(&runs, false), ("ab❯".to_string(), 3));
+    }
+
+    #[test]
+    fn test_layout() {
+        let panel = |title: &str, columns| Panel {
+            title: title.to_string(),
+            lines: vec![("a".to_string(), 1), ("bb".to_string(), 2)],
+            columns,
+        };
+        let output = layout(&[panel("x", 4), panel("y", 3), panel("z", 4)], 10);
+        assert_eq!(
+            output,
+            "x    │ y\n──── │ ───\na    │ a\nbb   │ bb\n\nz\n────\na\nbb\n"
+        );
+    }
+}
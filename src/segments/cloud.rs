@@ -0,0 +1,452 @@
+//! Kubernetes, AWS and Docker context segments, read from local config files.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use regex::Regex;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::{Segment, label};
+
+/// Matches `text` against a pattern where `*` stands for any run of characters.
+pub fn glob_match(pattern: &str, text: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == text;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if text.len() < first.len() + last.len() || !text.starts_with(first) || !text.ends_with(last) {
+        return false;
+    }
+    let mut rest = &text[first.len()..text.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(index) => rest = &rest[index + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Per-context colors: the first pattern matching the context name wins.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct ColorOverrides(pub Vec<(String, NamedColor)>);
+
+impl ColorOverrides {
+    pub fn color_for(&self, name: &str, default: NamedColor) -> NamedColor {
+        self.0
+            .iter()
+            .find(|(pattern, _)| glob_match(pattern, name))
+            .map(|(_, color)| *color)
+            .unwrap_or(default)
+    }
+}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    value
+        .strip_prefix('"')
+        .and_then(|v| v.strip_suffix('"'))
+        .or_else(|| value.strip_prefix('\'').and_then(|v| v.strip_suffix('\'')))
+        .unwrap_or(value)
+}
+
+/// The parts of a kubeconfig the segment needs.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct KubeConfig {
+    pub current_context: Option<String>,
+    /// Namespace of each context that sets one.
+    pub namespaces: HashMap<String, String>,
+}
+
+impl KubeConfig {
+    /// Parses the subset of kubeconfig YAML used here: the top-level
+    /// `current-context` and the `name`/`namespace` of each `contexts` entry.
+    pub fn parse(content: &str) -> KubeConfig {
+        let mut config = KubeConfig::default();
+        let mut in_contexts = false;
+        let mut name: Option<String> = None;
+        let mut namespace: Option<String> = None;
+        let mut flush = |name: &mut Option<String>, namespace: &mut Option<String>| {
+            if let (Some(n), Some(ns)) = (name.take(), namespace.take()) {
+                config.namespaces.insert(n, ns);
+            }
+            *namespace = None;
+        };
+
+        for line in content.lines() {
+            if line.trim().is_empty() || line.trim_start().starts_with('#') {
+                continue;
+            }
+            let top_level = !line.starts_with(' ') && !line.starts_with('-');
+            if top_level {
+                flush(&mut name, &mut namespace);
+                in_contexts = line.starts_with("contexts:");
+                if let Some(value) = line.strip_prefix("current-context:") {
+                    let value = unquote(value);
+                    if !value.is_empty() {
+                        config.current_context = Some(value.to_string());
+                    }
+                }
+                continue;
+            }
+            if !in_contexts {
+                continue;
+            }
+            let trimmed = line.trim_start();
+            let entry = match trimmed.strip_prefix("- ") {
+                Some(entry) => {
+                    flush(&mut name, &mut namespace);
+                    entry
+                }
+                None => trimmed,
+            };
+            if let Some((key, value)) = entry.split_once(':') {
+                match key.trim() {
+                    "name" => name = Some(unquote(value).to_string()),
+                    "namespace" => namespace = Some(unquote(value).to_string()),
+                    _ => {}
+                }
+            }
+        }
+        flush(&mut name, &mut namespace);
+        config
+    }
+}
+
+/// Kubernetes context and namespace from `$KUBECONFIG` or `~/.kube/config`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KubernetesSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+    pub overrides: ColorOverrides,
+}
+
+impl Default for KubernetesSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Kubernetes,
+            color: NamedColor::Blue,
+            overrides: ColorOverrides(vec![("prod*".to_string(), NamedColor::Red)]),
+        }
+    }
+}
+
+impl KubernetesSegment {
+    fn config_paths(ctx: &PromptContext) -> Vec<PathBuf> {
+        match ctx.var("KUBECONFIG") {
+            Some(paths) => paths
+                .split(':')
+                .filter(|p| !p.is_empty())
+                .map(PathBuf::from)
+                .collect(),
+            None => ctx
+                .home
+                .iter()
+                .map(|home| home.join(".kube/config"))
+                .collect(),
+        }
+    }
+
+    /// Returns `(context, namespace)`. Like kubectl, the first file that sets
+    /// `current-context` wins when `KUBECONFIG` lists several.
+    pub fn detect(ctx: &PromptContext) -> Option<(String, Option<String>)> {
+        let configs: Vec<KubeConfig> = Self::config_paths(ctx)
+            .iter()
+            .filter_map(|path| fs::read_to_string(path).ok())
+            .map(|content| KubeConfig::parse(&content))
+            .collect();
+        let current = configs
+            .iter()
+            .find_map(|config| config.current_context.clone())?;
+        let namespace = configs
+            .iter()
+            .find_map(|config| config.namespaces.get(&current).cloned());
+        Some((current, namespace))
+    }
+}
+
+impl Segment for KubernetesSegment {
+    fn name(&self) -> &str {
+        "kubernetes"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let (name, namespace) = Self::detect(ctx)?;
+        let text = match namespace {
+            Some(ns) => format!("{} ({})", name, ns),
+            None => name.clone(),
+        };
+        Some(label(
+            self.icon,
+            self.overrides.color_for(&name, self.color),
+            &text,
+        ))
+    }
+}
+
+/// Reads `key` from the `[section]` of an INI-style file such as `~/.aws/config`.
+pub fn ini_value(content: &str, section: &str, key: &str) -> Option<String> {
+    let mut in_section = false;
+    for line in content.lines() {
+        let line = line.trim();
+        if let Some(name) = line.strip_prefix('[').and_then(|l| l.strip_suffix(']')) {
+            in_section = name.trim() == section;
+        } else if in_section
+            && let Some((k, v)) = line.split_once('=')
+            && k.trim() == key
+        {
+            return Some(v.trim().to_string());
+        }
+    }
+    None
+}
+
+/// AWS profile (`AWS_PROFILE`) and its region.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AwsSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+    pub overrides: ColorOverrides,
+}
+
+impl Default for AwsSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Aws,
+            color: NamedColor::Yellow,
+            overrides: ColorOverrides(vec![("prod*".to_string(), NamedColor::Red)]),
+        }
+    }
+}
+
+impl AwsSegment {
+    /// Returns `(profile, region)`. Hidden unless a profile is selected.
+    pub fn detect(ctx: &PromptContext) -> Option<(String, Option<String>)> {
+        let profile = ctx
+            .var("AWS_PROFILE")
+            .or_else(|| ctx.var("AWS_DEFAULT_PROFILE"))?
+            .to_string();
+        let region = ctx
+            .var("AWS_REGION")
+            .or_else(|| ctx.var("AWS_DEFAULT_REGION"))
+            .map(str::to_string)
+            .or_else(|| {
+                let path = match ctx.var("AWS_CONFIG_FILE") {
+                    Some(path) => PathBuf::from(path),
+                    None => ctx.home.as_ref()?.join(".aws/config"),
+                };
+                let content = fs::read_to_string(path).ok()?;
+                // default 以外のプロファイルは "profile " 付きのセクション名になる
+                let section = if profile == "default" {
+                    profile.clone()
+                } else {
+                    format!("profile {}", profile)
+                };
+                ini_value(&content, &section, "region")
+            });
+        Some((profile, region))
+    }
+}
+
+impl Segment for AwsSegment {
+    fn name(&self) -> &str {
+        "aws"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let (profile, region) = Self::detect(ctx)?;
+        let text = match region {
+            Some(region) => format!("{} ({})", profile, region),
+            None => profile.clone(),
+        };
+        Some(label(
+            self.icon,
+            self.overrides.color_for(&profile, self.color),
+            &text,
+        ))
+    }
+}
+
+/// Docker context from `DOCKER_CONTEXT` or `~/.docker/config.json`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DockerSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+    pub overrides: ColorOverrides,
+}
+
+impl Default for DockerSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Docker,
+            color: NamedColor::Cyan,
+            overrides: ColorOverrides(vec![("prod*".to_string(), NamedColor::Red)]),
+        }
+    }
+}
+
+static CURRENT_CONTEXT: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""currentContext"\s*:\s*"([^"]*)""#).unwrap());
+
+impl DockerSegment {
+    /// Returns the active context, or `None` when it is `default`.
+    pub fn detect(ctx: &PromptContext) -> Option<String> {
+        let name = match ctx.var("DOCKER_CONTEXT") {
+            Some(name) => name.to_string(),
+            None => {
+                let dir = match ctx.var("DOCKER_CONFIG") {
+                    Some(dir) => PathBuf::from(dir),
+                    None => ctx.home.as_ref()?.join(".docker"),
+                };
+                let content = fs::read_to_string(dir.join("config.json")).ok()?;
+                CURRENT_CONTEXT
+                    .captures(&content)?
+                    .get(1)?
+                    .as_str()
+                    .to_string()
+            }
+        };
+        Some(name).filter(|name| !name.is_empty() && name != "default")
+    }
+}
+
+impl Segment for DockerSegment {
+    fn name(&self) -> &str {
+        "docker"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let name = Self::detect(ctx)?;
+        Some(label(
+            self.icon,
+            self.overrides.color_for(&name, self.color),
+            &name,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segments::tests::temp_dir;
+
+    const KUBECONFIG: &str = r#"apiVersion: v1
+clusters:
+- cluster:
+    server: https://example.com
+  name: prod-cluster
+contexts:
+- context:
+    cluster: prod-cluster
+    namespace: payments
+    user: admin
+  name: prod-eu
+- name: "dev"
+  context:
+    cluster: dev-cluster
+    user: dev
+current-context: prod-eu
+kind: Config
+"#;
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("prod*", "prod-eu"));
+        assert!(glob_match("*prod*", "eu-prod-1"));
+        assert!(glob_match("a*c*e", "abcde"));
+        assert!(!glob_match("prod*", "dev"));
+        assert!(!glob_match("a*a", "a"));
+        assert!(glob_match("dev", "dev"));
+    }
+
+    #[test]
+    fn test_parse_kubeconfig() {
+        let config = KubeConfig::parse(KUBECONFIG);
+        assert_eq!(config.current_context.as_deref(), Some("prod-eu"));
+        assert_eq!(
+            config.namespaces.get("prod-eu").map(String::as_str),
+            Some("payments")
+        );
+        assert_eq!(config.namespaces.get("dev"), None);
+        assert_eq!(config.namespaces.get("prod-cluster"), None);
+    }
+
+    #[test]
+    fn test_kubernetes_segment_from_kubeconfig_env() {
+        let dir = temp_dir("kube");
+        let empty = dir.join("empty");
+        let config = dir.join("config");
+        fs::write(&empty, "apiVersion: v1\n").unwrap();
+        fs::write(&config, KUBECONFIG).unwrap();
+        let ctx = PromptContext {
+            env: [(
+                "KUBECONFIG".to_string(),
+                format!("{}:{}", empty.display(), config.display()),
+            )]
+            .into(),
+            ..Default::default()
+        };
+        assert_eq!(
+            KubernetesSegment::default().render(&ctx).unwrap().build(),
+            "%{%F{red}%}%{%G☸%} prod-eu (payments)%{%f%}"
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_aws_segment() {
+        let home = temp_dir("aws");
+        fs::create_dir_all(home.join(".aws")).unwrap();
+        fs::write(
+            home.join(".aws/config"),
+            "[default]\nregion = us-east-1\n\n[profile staging]\nregion = eu-west-1\n",
+        )
+        .unwrap();
+        let mut ctx = PromptContext {
+            home: Some(home.clone()),
+            ..Default::default()
+        };
+        assert!(AwsSegment::default().render(&ctx).is_none());
+
+        ctx.env
+            .insert("AWS_PROFILE".to_string(), "staging".to_string());
+        assert_eq!(
+            AwsSegment::detect(&ctx),
+            Some(("staging".to_string(), Some("eu-west-1".to_string())))
+        );
+        ctx.env
+            .insert("AWS_REGION".to_string(), "ap-northeast-1".to_string());
+        assert_eq!(
+            AwsSegment::default().render(&ctx).unwrap().text(),
+            "☁ staging (ap-northeast-1)"
+        );
+        let _ = fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn test_docker_segment() {
+        let home = temp_dir("docker");
+        fs::create_dir_all(home.join(".docker")).unwrap();
+        fs::write(
+            home.join(".docker/config.json"),
+            r#"{"auths": {}, "currentContext": "colima"}"#,
+        )
+        .unwrap();
+        let mut ctx = PromptContext {
+            home: Some(home.clone()),
+            ..Default::default()
+        };
+        assert_eq!(DockerSegment::detect(&ctx).as_deref(), Some("colima"));
+
+        ctx.env
+            .insert("DOCKER_CONTEXT".to_string(), "default".to_string());
+        assert!(DockerSegment::default().render(&ctx).is_none());
+        let _ = fs::remove_dir_all(&home);
+    }
+}
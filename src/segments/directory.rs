@@ -0,0 +1,432 @@
+//! Working directory segment with fish-style shortening and truncation.
+
+use std::fs;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+
+use unicode_width::UnicodeWidthStr;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::Segment;
+use crate::segments::git;
+
+/// One displayed component of the shortened path.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathPart {
+    pub text: String,
+    /// The component is the root of the git repository containing the directory.
+    pub is_repo_root: bool,
+}
+
+impl PathPart {
+    fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            is_repo_root: false,
+        }
+    }
+}
+
+/// Renders the working directory, e.g. `~/p/r/zsh-seq`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectorySegment {
+    /// Abbreviate every component except the last (and the repository root).
+    pub shorten: bool,
+    /// Characters kept from each abbreviated component.
+    pub shorten_length: usize,
+    /// Show at most this many trailing components.
+    pub truncate_to: Option<usize>,
+    /// Shown in place of the components removed by truncation.
+    pub truncation_symbol: String,
+    /// When the terminal width is known, drop leading components until the path
+    /// takes at most this percentage of it. Off by default.
+    pub max_width_percent: Option<usize>,
+    /// Keep the repository root unabbreviated and draw it in `repo_root_color`.
+    pub highlight_repo_root: bool,
+    /// Appended when the directory is not writable by the current user.
+    pub read_only_icon: Option<Icon>,
+    /// Named directories (`hash -d name=path`) substituted as `~name`, in
+    /// addition to those of the context.
+    pub named_dirs: Vec<(String, PathBuf)>,
+    pub color: NamedColor,
+    pub repo_root_color: NamedColor,
+    pub read_only_color: NamedColor,
+}
+
+impl Default for DirectorySegment {
+    fn default() -> Self {
+        Self {
+            shorten: true,
+            shorten_length: 1,
+            truncate_to: None,
+            truncation_symbol: "…".to_string(),
+            max_width_percent: None,
+            highlight_repo_root: true,
+            read_only_icon: Some(Icon::Lock),
+            named_dirs: Vec::new(),
+            color: NamedColor::Cyan,
+            repo_root_color: NamedColor::LightCyan,
+            read_only_color: NamedColor::Red,
+        }
+    }
+}
+
+impl DirectorySegment {
+    /// Splits `cwd` into the parts to display. Joining the texts with `/` gives the
+    /// shortened path; a leading empty part stands for the filesystem root.
+    pub fn shorten_path(
+        &self,
+        cwd: &Path,
+        home: Option<&Path>,
+        repo_root: Option<&Path>,
+    ) -> Vec<PathPart> {
+        self.shorten_path_named(cwd, home, repo_root, &[])
+    }
+
+    /// Like `shorten_path`, also substituting `named_dirs`.
+    pub fn shorten_path_named(
+        &self,
+        cwd: &Path,
+        home: Option<&Path>,
+        repo_root: Option<&Path>,
+        named_dirs: &[(String, PathBuf)],
+    ) -> Vec<PathPart> {
+        // 最も長く一致する名前付きディレクトリ、次にホーム、最後にルートを起点にする
+        let mut anchors: Vec<(String, &Path)> = self
+            .named_dirs
+            .iter()
+            .chain(named_dirs)
+            .map(|(name, path)| (format!("~{}", name), path.as_path()))
+            .collect();
+        anchors.extend(home.map(|h| ("~".to_string(), h)));
+        let (anchor, base) = anchors
+            .into_iter()
+            .filter(|(_, path)| cwd.starts_with(path))
+            .max_by_key(|(_, path)| path.components().count())
+            .unwrap_or((String::new(), Path::new("/")));
+
+        let relative = cwd.strip_prefix(base).unwrap_or(cwd);
+        let mut parts = Vec::new();
+        let mut current = base.to_path_buf();
+        for component in relative.iter() {
+            current.push(component);
+            parts.push(PathPart {
+                text: component.to_string_lossy().into_owned(),
+                is_repo_root: repo_root == Some(current.as_path()),
+            });
+        }
+        if parts.is_empty() && anchor.is_empty() {
+            return vec![PathPart::new("/")];
+        }
+
+        let mut head = PathPart::new(anchor);
+        if let Some(max) = self.truncate_to
+            && parts.len() > max
+        {
+            parts.drain(..parts.len() - max);
+            head = PathPart::new(self.truncation_symbol.clone());
+        }
+
+        if self.shorten {
+            let last = parts.len().saturating_sub(1);
+            for part in parts.iter_mut().take(last) {
+                if !(self.highlight_repo_root && part.is_repo_root) {
+                    part.text = abbreviate(&part.text, self.shorten_length);
+                }
+            }
+        }
+
+        let mut result = vec![head];
+        result.extend(parts);
+        result
+    }
+
+    /// Drops leading components until the joined parts are at most
+    /// `max_width` columns wide. The last component, the repository root and a
+    /// leading `~` or `~name` are kept.
+    pub fn fit_width(&self, mut parts: Vec<PathPart>, max_width: usize) -> Vec<PathPart> {
+        let width = |parts: &[PathPart]| {
+            parts.iter().map(|p| p.text.width()).sum::<usize>() + parts.len().saturating_sub(1)
+        };
+        if parts.len() <= 2 || width(&parts) <= max_width {
+            return parts;
+        }
+        let keep_head = parts[0].text.starts_with('~');
+        let mut tail = parts.split_off(1);
+        let head = parts.pop().expect("parts has a head");
+        let symbol = PathPart::new(self.truncation_symbol.clone());
+        let assemble = |tail: &[PathPart]| {
+            let mut result = Vec::with_capacity(tail.len() + 2);
+            if keep_head {
+                result.push(head.clone());
+            }
+            result.push(symbol.clone());
+            result.extend_from_slice(tail);
+            result
+        };
+        loop {
+            let Some(i) = (0..tail.len() - 1).find(|i| !tail[*i].is_repo_root) else {
+                return assemble(&tail);
+            };
+            tail.remove(i);
+            let result = assemble(&tail);
+            if width(&result) <= max_width {
+                return result;
+            }
+        }
+    }
+}
+
+impl Segment for DirectorySegment {
+    fn name(&self) -> &str {
+        "directory"
+    }
+
+    /// Renders the context's working directory.
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let repo_root = if self.highlight_repo_root {
+            git::find_repo_root(&ctx.cwd)
+        } else {
+            None
+        };
+        let mut parts = self.shorten_path_named(
+            &ctx.cwd,
+            ctx.home.as_deref(),
+            repo_root.as_deref(),
+            &ctx.named_dirs,
+        );
+        if let (Some(percent), Some(columns)) = (self.max_width_percent, ctx.columns) {
+            parts = self.fit_width(parts, columns * percent / 100);
+        }
+
+        let mut builder = ZshPromptBuilder::new().color(self.color);
+        for (i, part) in parts.iter().enumerate() {
+            if i > 0 {
+                builder = builder.str("/");
+            }
+            if self.highlight_repo_root && part.is_repo_root {
+                builder = builder
+                    .bold()
+                    .color(self.repo_root_color)
+                    .str(&part.text)
+                    .color(self.color)
+                    .end_bold();
+            } else {
+                builder = builder.str(&part.text);
+            }
+        }
+        builder = builder.end_color();
+
+        if let Some(icon) = self.read_only_icon
+            && is_read_only(&ctx.cwd)
+        {
+            builder = builder
+                .icon_space()
+                .color(self.read_only_color)
+                .icon_glyph(icon)
+                .end_color();
+        }
+        Some(builder)
+    }
+}
+
+/// Keeps the first `length` characters, plus a leading dot for hidden directories.
+fn abbreviate(component: &str, length: usize) -> String {
+    let (prefix, rest) = match component.strip_prefix('.') {
+        Some(rest) => (".", rest),
+        None => ("", component),
+    };
+    format!(
+        "{}{}",
+        prefix,
+        rest.chars().take(length.max(1)).collect::<String>()
+    )
+}
+
+/// True if the current user has no write permission on `path`.
+fn is_read_only(path: &Path) -> bool {
+    let Ok(meta) = fs::metadata(path) else {
+        return false;
+    };
+    let uid = users::get_current_uid();
+    if uid == 0 {
+        return false;
+    }
+    let mode = meta.mode();
+    if meta.uid() == uid {
+        mode & 0o200 == 0
+    } else if in_group(meta.gid()) {
+        mode & 0o020 == 0
+    } else {
+        mode & 0o002 == 0
+    }
+}
+
+/// True if `gid` is the current user's primary or a supplementary group.
+fn in_group(gid: u32) -> bool {
+    let primary = users::get_current_gid();
+    if primary == gid {
+        return true;
+    }
+    // group_access_list は未使用の領域を gid 0 として返すので getgrouplist を使う
+    users::get_current_username()
+        .and_then(|name| users::get_user_groups(&name, primary))
+        .is_some_and(|groups| groups.iter().any(|group| group.gid() == gid))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn texts(parts: &[PathPart]) -> String {
+        parts
+            .iter()
+            .map(|p| p.text.as_str())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+
+    #[test]
+    fn test_fish_style_shortening() {
+        let segment = DirectorySegment::default();
+        let parts = segment.shorten_path(
+            Path::new("/home/me/projects/rust/zsh-seq"),
+            Some(Path::new("/home/me")),
+            None,
+        );
+        assert_eq!(texts(&parts), "~/p/r/zsh-seq");
+
+        let parts = segment.shorten_path(Path::new("/usr/local/.config/bin"), None, None);
+        assert_eq!(texts(&parts), "/u/l/.c/bin");
+
+        assert_eq!(
+            texts(&segment.shorten_path(Path::new("/"), None, None)),
+            "/"
+        );
+        assert_eq!(
+            texts(&segment.shorten_path(Path::new("/home/me"), Some(Path::new("/home/me")), None)),
+            "~"
+        );
+    }
+
+    #[test]
+    fn test_truncation() {
+        let segment = DirectorySegment {
+            shorten: false,
+            truncate_to: Some(2),
+            ..Default::default()
+        };
+        let parts = segment.shorten_path(Path::new("/a/b/c/d"), None, None);
+        assert_eq!(texts(&parts), "…/c/d");
+        let parts = segment.shorten_path(Path::new("/a/b"), None, None);
+        assert_eq!(texts(&parts), "/a/b");
+    }
+
+    #[test]
+    fn test_fit_width() {
+        let segment = DirectorySegment {
+            shorten: false,
+            ..Default::default()
+        };
+        let parts = segment.shorten_path(Path::new("/srv/www/example/htdocs"), None, None);
+        assert_eq!(
+            texts(&segment.fit_width(parts.clone(), 80)),
+            "/srv/www/example/htdocs"
+        );
+        assert_eq!(
+            texts(&segment.fit_width(parts.clone(), 16)),
+            "…/example/htdocs"
+        );
+        assert_eq!(texts(&segment.fit_width(parts, 3)), "…/htdocs");
+
+        // ~ とリポジトリルートは残す
+        let home = Path::new("/home/me");
+        let parts = segment.shorten_path(Path::new("/home/me/a/b/c/d"), Some(home), None);
+        assert_eq!(texts(&segment.fit_width(parts, 8)), "~/…/c/d");
+        let parts = segment.shorten_path(Path::new("/a/b/c/d/e"), None, Some(Path::new("/a/b")));
+        assert_eq!(texts(&segment.fit_width(parts, 7)), "…/b/d/e");
+    }
+
+    #[test]
+    fn test_repo_root_is_kept_and_marked() {
+        let segment = DirectorySegment::default();
+        let parts = segment.shorten_path(
+            Path::new("/home/me/src/zsh-seq/src/segments"),
+            Some(Path::new("/home/me")),
+            Some(Path::new("/home/me/src/zsh-seq")),
+        );
+        assert_eq!(texts(&parts), "~/s/zsh-seq/s/segments");
+        assert!(parts[2].is_repo_root);
+        assert!(!parts[1].is_repo_root);
+    }
+
+    #[test]
+    fn test_named_directories() {
+        let segment = DirectorySegment {
+            named_dirs: vec![("work".to_string(), PathBuf::from("/home/me/work"))],
+            ..Default::default()
+        };
+        let parts = segment.shorten_path(
+            Path::new("/home/me/work/api/server"),
+            Some(Path::new("/home/me")),
+            None,
+        );
+        assert_eq!(texts(&parts), "~work/a/server");
+
+        // zsh から渡された名前付きディレクトリも使う
+        let ctx = PromptContext {
+            cwd: PathBuf::from("/srv/www/site"),
+            named_dirs: vec![("www".to_string(), PathBuf::from("/srv/www"))],
+            ..Default::default()
+        };
+        let segment = DirectorySegment {
+            read_only_icon: None,
+            ..Default::default()
+        };
+        assert_eq!(segment.render(&ctx).unwrap().text(), "~www/site");
+    }
+
+    #[test]
+    fn test_in_group() {
+        assert!(in_group(users::get_current_gid()));
+        if let Some(groups) = users::get_current_username()
+            .and_then(|name| users::get_user_groups(&name, users::get_current_gid()))
+        {
+            assert!(groups.iter().all(|group| in_group(group.gid())));
+        }
+    }
+
+    #[test]
+    fn test_render_highlights_repo_root() {
+        let segment = DirectorySegment {
+            read_only_icon: None,
+            ..Default::default()
+        };
+        let ctx = PromptContext {
+            cwd: PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("src"),
+            home: PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .parent()
+                .map(Path::to_path_buf),
+            ..Default::default()
+        };
+        // git 管理外に展開された場合はリポジトリルートを強調しない
+        let Some(root) = git::find_repo_root(&ctx.cwd) else {
+            return;
+        };
+        if root != Path::new(env!("CARGO_MANIFEST_DIR")) {
+            return;
+        }
+        let name = root.file_name().unwrap().to_string_lossy();
+        let prompt = segment.render(&ctx).unwrap().build();
+        assert_eq!(
+            prompt,
+            format!(
+                "%{{%F{{cyan}}%}}~/%{{%B%}}%{{%F{{lightcyan}}%}}{}%{{%F{{cyan}}%}}%{{%b%}}/src%{{%f%}}",
+                name
+            )
+        );
+    }
+}
@@ -0,0 +1,141 @@
+//! Last-command duration segment ("took 3.2s").
+
+use std::time::Duration;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::{DURATION_ENV, PromptContext};
+use crate::segments::Segment;
+
+/// Formats a duration compactly: `850ms`, `3.2s`, `1m 5s`, `2h 3m`.
+///
+/// Durations of a second or more are rounded to a tenth of a second before
+/// the unit is chosen, so 59.95 s becomes `1m 0s` rather than `60.0s`.
+pub fn format_duration(duration: Duration) -> String {
+    let millis = duration.as_millis();
+    let tenths = (millis + 50) / 100;
+    let secs = tenths / 10;
+    if millis < 1000 {
+        format!("{}ms", millis)
+    } else if secs < 60 {
+        format!("{}.{}s", secs, tenths % 10)
+    } else if secs < 3600 {
+        format!("{}m {}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+/// Shows how long the last command took, once it exceeds `threshold`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DurationSegment {
+    pub threshold: Duration,
+    pub prefix: String,
+    pub color: NamedColor,
+}
+
+impl Default for DurationSegment {
+    fn default() -> Self {
+        Self {
+            threshold: Duration::from_secs(2),
+            prefix: "took ".to_string(),
+            color: NamedColor::Yellow,
+        }
+    }
+}
+
+impl Segment for DurationSegment {
+    fn name(&self) -> &str {
+        "duration"
+    }
+
+    /// Renders `ctx.duration`. Returns `None` if it is unknown or below the threshold.
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let duration = ctx.duration.filter(|d| *d >= self.threshold)?;
+        Some(
+            ZshPromptBuilder::new()
+                .color(self.color)
+                .str(&self.prefix)
+                .str(&format_duration(duration))
+                .end_color(),
+        )
+    }
+}
+
+/// Generates zsh `preexec`/`precmd` hooks that measure each command with
+/// `$EPOCHREALTIME` and export the result as `ZSH_SEQ_DURATION_MS`, which
+/// `PromptContext::current` picks up.
+///
+/// The variable is empty when no command ran (e.g. an empty line was entered).
+/// `zsh_seq_cli init zsh` includes these hooks.
+pub fn zsh_hooks() -> String {
+    format!(
+        r#"zmodload zsh/datetime
+typeset -g _zsh_seq_cmd_start=
+typeset -gx {env}=
+_zsh_seq_duration_preexec() {{
+    _zsh_seq_cmd_start=$EPOCHREALTIME
+}}
+_zsh_seq_duration_precmd() {{
+    if [[ -n $_zsh_seq_cmd_start ]]; then
+        printf -v {env} '%.0f' $(( (EPOCHREALTIME - _zsh_seq_cmd_start) * 1000 ))
+        _zsh_seq_cmd_start=
+    else
+        {env}=
+    fi
+}}
+autoload -Uz add-zsh-hook
+add-zsh-hook preexec _zsh_seq_duration_preexec
+add-zsh-hook precmd _zsh_seq_duration_precmd
+"#,
+        env = DURATION_ENV
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_duration() {
+        assert_eq!(format_duration(Duration::from_millis(850)), "850ms");
+        assert_eq!(format_duration(Duration::from_millis(3240)), "3.2s");
+        assert_eq!(format_duration(Duration::from_secs(65)), "1m 5s");
+        // 単位を選ぶ前に丸める
+        assert_eq!(format_duration(Duration::from_millis(59_950)), "1m 0s");
+        assert_eq!(format_duration(Duration::from_millis(59_940)), "59.9s");
+        assert_eq!(format_duration(Duration::from_millis(3_599_960)), "1h 0m");
+        assert_eq!(
+            format_duration(Duration::from_secs(2 * 3600 + 180)),
+            "2h 3m"
+        );
+    }
+
+    #[test]
+    fn test_render_respects_threshold() {
+        let segment = DurationSegment::default();
+        let short = PromptContext {
+            duration: Some(Duration::from_millis(500)),
+            ..Default::default()
+        };
+        assert!(segment.render(&short).is_none());
+        assert!(segment.render(&PromptContext::default()).is_none());
+
+        let long = PromptContext {
+            duration: Some(Duration::from_millis(3200)),
+            ..Default::default()
+        };
+        assert_eq!(
+            segment.render(&long).unwrap().build(),
+            "%{%F{yellow}%}took 3.2s%{%f%}"
+        );
+    }
+
+    #[test]
+    fn test_zsh_hooks_register_both_hooks() {
+        let hooks = zsh_hooks();
+        assert!(hooks.contains("add-zsh-hook preexec _zsh_seq_duration_preexec\n"));
+        assert!(hooks.contains("add-zsh-hook precmd _zsh_seq_duration_precmd\n"));
+        assert!(hooks.contains("printf -v ZSH_SEQ_DURATION_MS '%.0f'"));
+    }
+}
@@ -0,0 +1,229 @@
+//! Segments for activated environments: Python virtualenv, conda, nix-shell and direnv.
+//!
+//! Each one reads the variables its tool exports and hides itself when inactive.
+
+use std::path::Path;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::{Segment, label};
+
+fn basename(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.to_string())
+}
+
+/// Python virtualenv (`VIRTUAL_ENV`, `VIRTUAL_ENV_PROMPT`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PythonSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+}
+
+impl Default for PythonSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Python,
+            color: NamedColor::Yellow,
+        }
+    }
+}
+
+impl PythonSegment {
+    /// The environment name, preferring the prompt chosen with `--prompt`.
+    pub fn detect(ctx: &PromptContext) -> Option<String> {
+        let venv = ctx.var("VIRTUAL_ENV")?;
+        let name = ctx
+            .var("VIRTUAL_ENV_PROMPT")
+            .map(|prompt| prompt.trim().trim_start_matches('(').trim_end_matches(')'))
+            .filter(|prompt| !prompt.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| basename(venv));
+        Some(name)
+    }
+}
+
+impl Segment for PythonSegment {
+    fn name(&self) -> &str {
+        "virtualenv"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        Self::detect(ctx).map(|name| label(self.icon, self.color, &name))
+    }
+}
+
+/// Conda environment (`CONDA_DEFAULT_ENV`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CondaSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+    /// Also show the `base` environment, which is usually always active.
+    pub show_base: bool,
+}
+
+impl Default for CondaSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Conda,
+            color: NamedColor::Green,
+            show_base: false,
+        }
+    }
+}
+
+impl Segment for CondaSegment {
+    fn name(&self) -> &str {
+        "conda"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let env = ctx.var("CONDA_DEFAULT_ENV")?;
+        if env == "base" && !self.show_base {
+            return None;
+        }
+        Some(label(self.icon, self.color, &basename(env)))
+    }
+}
+
+/// nix-shell / `nix develop` (`IN_NIX_SHELL`, `name`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NixShellSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+}
+
+impl Default for NixShellSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Nix,
+            color: NamedColor::Blue,
+        }
+    }
+}
+
+impl Segment for NixShellSegment {
+    fn name(&self) -> &str {
+        "nix_shell"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let kind = ctx.var("IN_NIX_SHELL")?;
+        let name = ctx.var("name").unwrap_or("nix-shell");
+        let text = match kind {
+            "pure" | "impure" => format!("{} ({})", name, kind),
+            _ => name.to_string(),
+        };
+        Some(label(self.icon, self.color, &text))
+    }
+}
+
+/// direnv (`DIRENV_DIR`), showing the directory whose `.envrc` is loaded.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirenvSegment {
+    pub icon: Icon,
+    pub color: NamedColor,
+}
+
+impl Default for DirenvSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Direnv,
+            color: NamedColor::LightBlack,
+        }
+    }
+}
+
+impl Segment for DirenvSegment {
+    fn name(&self) -> &str {
+        "direnv"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        // direnv は先頭に '-' を付けてディレクトリを書き出す
+        let dir = ctx.var("DIRENV_DIR")?;
+        let dir = dir.strip_prefix('-').unwrap_or(dir);
+        Some(label(self.icon, self.color, &basename(dir)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ctx(vars: &[(&str, &str)]) -> PromptContext {
+        PromptContext {
+            env: vars
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_inactive_segments_are_hidden() {
+        let empty = ctx(&[("VIRTUAL_ENV", "")]);
+        assert!(PythonSegment::default().render(&empty).is_none());
+        assert!(CondaSegment::default().render(&empty).is_none());
+        assert!(NixShellSegment::default().render(&empty).is_none());
+        assert!(DirenvSegment::default().render(&empty).is_none());
+    }
+
+    #[test]
+    fn test_python_virtualenv() {
+        let venv = ctx(&[("VIRTUAL_ENV", "/home/me/project/.venv")]);
+        assert_eq!(
+            PythonSegment::default().render(&venv).unwrap().text(),
+            "🐍 .venv"
+        );
+        let prompt = ctx(&[
+            ("VIRTUAL_ENV", "/home/me/project/.venv"),
+            ("VIRTUAL_ENV_PROMPT", "(project) "),
+        ]);
+        assert_eq!(PythonSegment::detect(&prompt).as_deref(), Some("project"));
+    }
+
+    #[test]
+    fn test_conda_hides_base() {
+        let base = ctx(&[("CONDA_DEFAULT_ENV", "base")]);
+        assert!(CondaSegment::default().render(&base).is_none());
+        let show = CondaSegment {
+            show_base: true,
+            ..Default::default()
+        };
+        assert_eq!(show.render(&base).unwrap().text(), "🅒 base");
+        let ml = ctx(&[("CONDA_DEFAULT_ENV", "ml")]);
+        assert_eq!(
+            CondaSegment::default().render(&ml).unwrap().build(),
+            "%{%F{green}%}%{%G🅒%} ml%{%f%}"
+        );
+    }
+
+    #[test]
+    fn test_nix_shell() {
+        let shell = ctx(&[("IN_NIX_SHELL", "impure"), ("name", "dev-env")]);
+        assert_eq!(
+            NixShellSegment::default().render(&shell).unwrap().text(),
+            "❄ dev-env (impure)"
+        );
+        let flake = ctx(&[("IN_NIX_SHELL", "1")]);
+        assert_eq!(
+            NixShellSegment::default().render(&flake).unwrap().text(),
+            "❄ nix-shell"
+        );
+    }
+
+    #[test]
+    fn test_direnv() {
+        let direnv = ctx(&[("DIRENV_DIR", "-/home/me/api")]);
+        assert_eq!(
+            DirenvSegment::default().render(&direnv).unwrap().text(),
+            "📂 api"
+        );
+    }
+}
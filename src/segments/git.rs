@@ -0,0 +1,468 @@
+//! Git repository status segment.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::Segment;
+
+/// An operation that is in progress in the repository.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GitOperation {
+    Merge,
+    Rebase,
+    ApplyMailbox,
+    CherryPick,
+    Revert,
+    Bisect,
+}
+
+impl GitOperation {
+    /// The label git itself uses in `git status`.
+    pub fn label(&self) -> &'static str {
+        match self {
+            GitOperation::Merge => "MERGING",
+            GitOperation::Rebase => "REBASING",
+            GitOperation::ApplyMailbox => "AM",
+            GitOperation::CherryPick => "CHERRY-PICKING",
+            GitOperation::Revert => "REVERTING",
+            GitOperation::Bisect => "BISECTING",
+        }
+    }
+}
+
+/// A snapshot of the repository state.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GitStatus {
+    /// Current branch, `None` for a detached HEAD.
+    pub branch: Option<String>,
+    /// Abbreviated commit id of HEAD, `None` before the first commit.
+    pub commit: Option<String>,
+    pub upstream: Option<String>,
+    pub ahead: usize,
+    pub behind: usize,
+    pub staged: usize,
+    pub unstaged: usize,
+    pub untracked: usize,
+    pub conflicted: usize,
+    pub stashes: usize,
+    pub operation: Option<GitOperation>,
+}
+
+impl GitStatus {
+    /// Reads the status of the repository containing `dir`.
+    ///
+    /// Returns `None` outside a repository or if `git` cannot be run.
+    pub fn read(dir: &Path) -> Option<GitStatus> {
+        let git_dir = find_git_dir(dir)?;
+        let output = Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["status", "--porcelain=v2", "--branch"])
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        let mut status = GitStatus::parse_porcelain_v2(&String::from_utf8_lossy(&output.stdout));
+        status.operation = detect_operation(&git_dir);
+        status.stashes = count_stashes(&git_dir);
+        Some(status)
+    }
+
+    /// Parses the output of `git status --porcelain=v2 --branch`.
+    pub fn parse_porcelain_v2(output: &str) -> GitStatus {
+        let mut status = GitStatus::default();
+        for line in output.lines() {
+            if let Some(header) = line.strip_prefix("# ") {
+                let (key, value) = header.split_once(' ').unwrap_or((header, ""));
+                match key {
+                    "branch.oid" if value != "(initial)" => {
+                        status.commit = Some(value.chars().take(7).collect());
+                    }
+                    "branch.head" if value != "(detached)" => {
+                        status.branch = Some(value.to_string());
+                    }
+                    "branch.upstream" => status.upstream = Some(value.to_string()),
+                    "branch.ab" => {
+                        for part in value.split_whitespace() {
+                            if let Some(n) = part.strip_prefix('+') {
+                                status.ahead = n.parse().unwrap_or(0);
+                            } else if let Some(n) = part.strip_prefix('-') {
+                                status.behind = n.parse().unwrap_or(0);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+                continue;
+            }
+            match line.split_once(' ') {
+                Some(("1" | "2", rest)) => {
+                    let mut xy = rest.chars();
+                    if xy.next().is_some_and(|x| x != '.') {
+                        status.staged += 1;
+                    }
+                    if xy.next().is_some_and(|y| y != '.') {
+                        status.unstaged += 1;
+                    }
+                }
+                Some(("u", _)) => status.conflicted += 1,
+                Some(("?", _)) => status.untracked += 1,
+                _ => {}
+            }
+        }
+        status
+    }
+
+    /// True if there is nothing to commit and nothing untracked.
+    pub fn is_clean(&self) -> bool {
+        self.staged == 0 && self.unstaged == 0 && self.untracked == 0 && self.conflicted == 0
+    }
+}
+
+/// Walks up from `dir` to find the git directory, following `gitdir:` files
+/// used by worktrees and submodules.
+pub fn find_git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let content = fs::read_to_string(&dot_git).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim();
+            return Some(ancestor.join(target));
+        }
+    }
+    None
+}
+
+/// Walks up from `dir` to the top of the working tree: the directory holding
+/// `.git`, which may be a `gitdir:` file in worktrees and submodules.
+pub fn find_repo_root(dir: &Path) -> Option<PathBuf> {
+    dir.ancestors()
+        .find(|ancestor| ancestor.join(".git").exists())
+        .map(Path::to_path_buf)
+}
+
+/// Directory holding shared refs; differs from `git_dir` for linked worktrees.
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match fs::read_to_string(git_dir.join("commondir")) {
+        Ok(content) => git_dir.join(content.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+fn detect_operation(git_dir: &Path) -> Option<GitOperation> {
+    if git_dir.join("rebase-merge").is_dir() {
+        Some(GitOperation::Rebase)
+    } else if git_dir.join("rebase-apply").is_dir() {
+        if git_dir.join("rebase-apply/applying").exists() {
+            Some(GitOperation::ApplyMailbox)
+        } else {
+            Some(GitOperation::Rebase)
+        }
+    } else if git_dir.join("MERGE_HEAD").exists() {
+        Some(GitOperation::Merge)
+    } else if git_dir.join("CHERRY_PICK_HEAD").exists() {
+        Some(GitOperation::CherryPick)
+    } else if git_dir.join("REVERT_HEAD").exists() {
+        Some(GitOperation::Revert)
+    } else if git_dir.join("BISECT_LOG").exists() {
+        Some(GitOperation::Bisect)
+    } else {
+        None
+    }
+}
+
+/// Each stash entry is one line of the `refs/stash` reflog.
+fn count_stashes(git_dir: &Path) -> usize {
+    fs::read_to_string(common_dir(git_dir).join("logs/refs/stash"))
+        .map(|log| log.lines().filter(|line| !line.is_empty()).count())
+        .unwrap_or(0)
+}
+
+/// Symbols placed in front of each part of the segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitSymbols {
+    pub branch: String,
+    pub detached: String,
+    pub ahead: String,
+    pub behind: String,
+    pub staged: String,
+    pub unstaged: String,
+    pub untracked: String,
+    pub conflicted: String,
+    pub stash: String,
+}
+
+impl Default for GitSymbols {
+    fn default() -> Self {
+        Self {
+            branch: String::new(),
+            detached: "@".to_string(),
+            ahead: "↑".to_string(),
+            behind: "↓".to_string(),
+            staged: "+".to_string(),
+            unstaged: "!".to_string(),
+            untracked: "?".to_string(),
+            conflicted: "=".to_string(),
+            stash: "$".to_string(),
+        }
+    }
+}
+
+/// Colors for each part of the segment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct GitColors {
+    pub clean: NamedColor,
+    pub dirty: NamedColor,
+    pub detached: NamedColor,
+    pub operation: NamedColor,
+    pub counts: NamedColor,
+}
+
+impl Default for GitColors {
+    fn default() -> Self {
+        Self {
+            clean: NamedColor::Green,
+            dirty: NamedColor::Yellow,
+            detached: NamedColor::Cyan,
+            operation: NamedColor::Red,
+            counts: NamedColor::LightBlack,
+        }
+    }
+}
+
+/// Renders the repository state, e.g. `main|MERGING ↑1 +2 !1 ?3 $1`.
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct GitSegment {
+    pub symbols: GitSymbols,
+    pub colors: GitColors,
+    /// Drawn in front of the branch name, e.g. `Icon::GitBranch`.
+    pub branch_icon: Option<Icon>,
+}
+
+impl GitSegment {
+    /// Renders an already-read status.
+    pub fn render_status(&self, status: &GitStatus) -> ZshPromptBuilder {
+        let symbols = &self.symbols;
+        let (head, color) = match &status.branch {
+            Some(branch) => {
+                let color = if status.is_clean() {
+                    self.colors.clean
+                } else {
+                    self.colors.dirty
+                };
+                (format!("{}{}", symbols.branch, branch), color)
+            }
+            None => (
+                format!(
+                    "{}{}",
+                    symbols.detached,
+                    status.commit.as_deref().unwrap_or("HEAD")
+                ),
+                self.colors.detached,
+            ),
+        };
+        let mut builder = ZshPromptBuilder::new().color(color);
+        if let Some(icon) = self.branch_icon
+            && status.branch.is_some()
+        {
+            builder = builder.icon(icon);
+        }
+        builder = builder.str(&head).end_color();
+
+        if let Some(operation) = status.operation {
+            builder = builder
+                .color(self.colors.operation)
+                .str("|")
+                .str(operation.label())
+                .end_color();
+        }
+
+        let counts: Vec<String> = [
+            (status.ahead, &symbols.ahead),
+            (status.behind, &symbols.behind),
+            (status.conflicted, &symbols.conflicted),
+            (status.staged, &symbols.staged),
+            (status.unstaged, &symbols.unstaged),
+            (status.untracked, &symbols.untracked),
+            (status.stashes, &symbols.stash),
+        ]
+        .iter()
+        .filter(|(count, _)| *count > 0)
+        .map(|(count, symbol)| format!("{}{}", symbol, count))
+        .collect();
+        if !counts.is_empty() {
+            builder = builder
+                .str(" ")
+                .color(self.colors.counts)
+                .str(&counts.join(" "))
+                .end_color();
+        }
+        builder
+    }
+}
+
+impl Segment for GitSegment {
+    fn name(&self) -> &str {
+        "git"
+    }
+
+    /// Reads the repository at the context's working directory.
+    /// Returns `None` outside a repository.
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        GitStatus::read(&ctx.cwd).map(|status| self.render_status(&status))
+    }
+
+    fn timeout_hint(&self) -> Option<Duration> {
+        Some(Duration::from_millis(200))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segments::tests::temp_dir;
+
+    const PORCELAIN: &str = "\
+# branch.oid 1234567890abcdef1234567890abcdef12345678
+# branch.head main
+# branch.upstream origin/main
+# branch.ab +2 -1
+1 M. N... 100644 100644 100644 abc abc src/a.rs
+1 .M N... 100644 100644 100644 abc abc src/b.rs
+1 MM N... 100644 100644 100644 abc abc src/c.rs
+2 R. N... 100644 100644 100644 abc abc R100 new.rs\told.rs
+u UU N... 100644 100644 100644 100644 abc abc abc conflict.rs
+? untracked.txt
+? other.txt
+! ignored.log
+";
+
+    #[test]
+    fn test_parse_porcelain_v2() {
+        let status = GitStatus::parse_porcelain_v2(PORCELAIN);
+        assert_eq!(
+            status,
+            GitStatus {
+                branch: Some("main".to_string()),
+                commit: Some("1234567".to_string()),
+                upstream: Some("origin/main".to_string()),
+                ahead: 2,
+                behind: 1,
+                staged: 3,
+                unstaged: 2,
+                untracked: 2,
+                conflicted: 1,
+                stashes: 0,
+                operation: None,
+            }
+        );
+    }
+
+    #[test]
+    fn test_parse_detached_and_initial() {
+        let detached = GitStatus::parse_porcelain_v2(
+            "# branch.oid abcdef0123456789\n# branch.head (detached)\n",
+        );
+        assert_eq!(detached.branch, None);
+        assert_eq!(detached.commit.as_deref(), Some("abcdef0"));
+
+        let initial = GitStatus::parse_porcelain_v2("# branch.oid (initial)\n# branch.head main\n");
+        assert_eq!(initial.commit, None);
+        assert!(initial.is_clean());
+    }
+
+    #[test]
+    fn test_render_status() {
+        let status = GitStatus {
+            branch: Some("main".to_string()),
+            ahead: 1,
+            untracked: 3,
+            stashes: 1,
+            operation: Some(GitOperation::Merge),
+            ..Default::default()
+        };
+        let prompt = GitSegment::default().render_status(&status).build();
+        assert_eq!(
+            prompt,
+            "%{%F{yellow}%}main%{%f%}%{%F{red}%}|MERGING%{%f%} %{%F{240}%}%{%G↑%}1 ?3 $1%{%f%}"
+        );
+
+        let detached = GitStatus {
+            commit: Some("abcdef0".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(
+            GitSegment::default().render_status(&detached).text(),
+            "@abcdef0"
+        );
+    }
+
+    fn git(dir: &Path, args: &[&str]) -> bool {
+        Command::new("git")
+            .arg("-C")
+            .arg(dir)
+            .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+            .args(args)
+            .output()
+            .is_ok_and(|out| out.status.success())
+    }
+
+    #[test]
+    fn test_read_temporary_repository() {
+        let dir = temp_dir("git");
+        // git が使えない環境ではスキップする
+        if !git(&dir, &["init", "-q", "-b", "main"]) {
+            return;
+        }
+        fs::write(dir.join("a.txt"), "a").unwrap();
+        assert!(git(&dir, &["add", "a.txt"]));
+        assert!(git(&dir, &["commit", "-q", "-m", "init"]));
+        fs::write(dir.join("a.txt"), "changed").unwrap();
+        assert!(git(&dir, &["stash", "-q"]));
+        fs::write(dir.join("b.txt"), "b").unwrap();
+        fs::write(dir.join(".git/MERGE_HEAD"), "").unwrap();
+
+        let sub = dir.join("sub");
+        fs::create_dir(&sub).unwrap();
+        let status = GitStatus::read(&sub).unwrap();
+        assert_eq!(status.branch.as_deref(), Some("main"));
+        assert_eq!(status.untracked, 1);
+        assert_eq!(status.stashes, 1);
+        assert_eq!(status.operation, Some(GitOperation::Merge));
+
+        let outside = temp_dir("not-git");
+        assert_eq!(GitStatus::read(&outside), None);
+
+        let _ = fs::remove_dir_all(&dir);
+        let _ = fs::remove_dir_all(&outside);
+    }
+
+    #[test]
+    fn test_find_repo_root_with_gitdir_file() {
+        let dir = temp_dir("worktree");
+        let sub = dir.join("tree/src");
+        fs::create_dir_all(&sub).unwrap();
+        // リンクされたワークツリーでは .git がファイルになる
+        fs::write(
+            dir.join("tree/.git"),
+            "gitdir: ../main/.git/worktrees/tree\n",
+        )
+        .unwrap();
+        assert_eq!(find_repo_root(&sub), Some(dir.join("tree")));
+        assert_eq!(
+            find_git_dir(&sub),
+            Some(dir.join("tree/../main/.git/worktrees/tree"))
+        );
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,246 @@
+//! Ready-made prompt segments that render into `ZshPromptBuilder`s, and the
+//! `Segment` trait and registry that let prompts be assembled from segment names.
+
+pub mod cloud;
+pub mod directory;
+pub mod duration;
+pub mod environment;
+pub mod git;
+pub mod status;
+pub mod toolchain;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+
+/// A pluggable piece of a prompt.
+///
+/// Third-party crates can implement this and add their segments to a `SegmentRegistry`.
+pub trait Segment: Send + Sync {
+    /// Name used to refer to the segment in prompt definitions.
+    fn name(&self) -> &str;
+
+    /// Renders the segment, or returns `None` if it has nothing to show.
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder>;
+
+    /// How long rendering may take in the worst case (e.g. when it runs a
+    /// process), so callers can decide whether to render it asynchronously.
+    fn timeout_hint(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// A segment that always renders the same sequences, e.g. `%n` or `%#`.
+pub struct StaticSegment {
+    name: String,
+    builder: ZshPromptBuilder,
+}
+
+impl StaticSegment {
+    pub fn new(name: &str, builder: ZshPromptBuilder) -> Self {
+        Self {
+            name: name.to_string(),
+            builder,
+        }
+    }
+}
+
+impl Segment for StaticSegment {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn render(&self, _ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        Some(self.builder.clone())
+    }
+}
+
+/// Returned when a prompt refers to a segment that is not registered.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnknownSegment(pub String);
+
+impl std::fmt::Display for UnknownSegment {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "unknown segment `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownSegment {}
+
+/// Segments looked up by name.
+#[derive(Default)]
+pub struct SegmentRegistry {
+    segments: HashMap<String, Box<dyn Segment>>,
+}
+
+impl SegmentRegistry {
+    /// Creates an empty registry.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry with every built-in segment in its default configuration.
+    pub fn with_builtins() -> Self {
+        use crate::segments::toolchain::{Language, ToolchainSegment};
+
+        let mut registry = Self::new();
+        registry.register(StaticSegment::new(
+            "username",
+            ZshPromptBuilder::new().username(),
+        ));
+        registry.register(StaticSegment::new(
+            "hostname",
+            ZshPromptBuilder::new().hostname_short(),
+        ));
+        registry.register(StaticSegment::new(
+            "privileged",
+            ZshPromptBuilder::new().privileged_indicator(),
+        ));
+        registry.register(directory::DirectorySegment::default());
+        registry.register(git::GitSegment::default());
+        registry.register(duration::DurationSegment::default());
+        registry.register(status::StatusSegment::default());
+        registry.register(status::JobsSegment::default());
+        registry.register(status::KeymapSegment::default());
+        registry.register(environment::PythonSegment::default());
+        registry.register(environment::CondaSegment::default());
+        registry.register(environment::NixShellSegment::default());
+        registry.register(environment::DirenvSegment::default());
+        registry.register(ToolchainSegment::new(Language::ALL.to_vec()));
+        for language in Language::ALL {
+            registry.register(ToolchainSegment::new(vec![language]));
+        }
+        registry.register(cloud::KubernetesSegment::default());
+        registry.register(cloud::AwsSegment::default());
+        registry.register(cloud::DockerSegment::default());
+        registry
+    }
+
+    /// Adds a segment, replacing any previous one with the same name.
+    pub fn register(&mut self, segment: impl Segment + 'static) {
+        self.segments
+            .insert(segment.name().to_string(), Box::new(segment));
+    }
+
+    pub fn get(&self, name: &str) -> Option<&dyn Segment> {
+        self.segments.get(name).map(|segment| segment.as_ref())
+    }
+
+    /// Names of all registered segments, sorted.
+    pub fn names(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.segments.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Renders the named segments in order and joins the visible ones with `separator`.
+    pub fn render(
+        &self,
+        names: &[&str],
+        ctx: &PromptContext,
+        separator: &ZshPromptBuilder,
+    ) -> Result<ZshPromptBuilder, UnknownSegment> {
+        let mut parts = Vec::with_capacity(names.len());
+        for name in names {
+            let segment = self
+                .get(name)
+                .ok_or_else(|| UnknownSegment(name.to_string()))?;
+            parts.push(segment.render(ctx));
+        }
+        Ok(ZshPromptBuilder::new().join(parts.into_iter().flatten(), separator))
+    }
+}
+
+/// An icon and a text in one color, as most context segments render.
+pub(crate) fn label(icon: Icon, color: NamedColor, text: &str) -> ZshPromptBuilder {
+    ZshPromptBuilder::new()
+        .color(color)
+        .icon(icon)
+        .str(text)
+        .end_color()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Creates an empty directory under the system temp dir.
+    pub(crate) fn temp_dir(name: &str) -> PathBuf {
+        static COUNTER: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "zsh-seq-{}-{}-{}",
+            name,
+            std::process::id(),
+            COUNTER.fetch_add(1, Ordering::Relaxed)
+        ));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    struct Fixed(&'static str, Option<&'static str>);
+
+    impl Segment for Fixed {
+        fn name(&self) -> &str {
+            self.0
+        }
+
+        fn render(&self, _ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+            self.1.map(|text| ZshPromptBuilder::new().str(text))
+        }
+    }
+
+    #[test]
+    fn test_registry_skips_empty_segments() {
+        let mut registry = SegmentRegistry::new();
+        registry.register(Fixed("a", Some("A")));
+        registry.register(Fixed("empty", None));
+        registry.register(Fixed("b", Some("B")));
+        let separator = ZshPromptBuilder::new().str(" | ");
+        let prompt = registry
+            .render(
+                &["a", "empty", "b", "empty"],
+                &PromptContext::default(),
+                &separator,
+            )
+            .unwrap();
+        assert_eq!(prompt.build(), "A | B");
+        assert_eq!(
+            registry
+                .render(&["a", "missing"], &PromptContext::default(), &separator)
+                .err(),
+            Some(UnknownSegment("missing".to_string()))
+        );
+    }
+
+    #[test]
+    fn test_register_replaces_by_name() {
+        let mut registry = SegmentRegistry::with_builtins();
+        assert!(registry.names().contains(&"git"));
+        assert!(registry.names().contains(&"rust"));
+        registry.register(Fixed("git", Some("custom")));
+        let prompt = registry
+            .render(
+                &["git"],
+                &PromptContext::default(),
+                &ZshPromptBuilder::new(),
+            )
+            .unwrap();
+        assert_eq!(prompt.build(), "custom");
+        assert_eq!(registry.get("git").unwrap().timeout_hint(), None);
+        assert!(
+            SegmentRegistry::with_builtins()
+                .get("git")
+                .unwrap()
+                .timeout_hint()
+                .is_some()
+        );
+    }
+}
@@ -0,0 +1,303 @@
+//! Exit-status segment with signal name decoding and `$pipestatus` support,
+//! plus the background job count and the vi keymap indicator.
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::Segment;
+use crate::sequences::ZshCondition;
+
+/// Returns the name of a (Linux) signal number.
+pub fn signal_name(signal: i32) -> Option<&'static str> {
+    let name = match signal {
+        1 => "SIGHUP",
+        2 => "SIGINT",
+        3 => "SIGQUIT",
+        4 => "SIGILL",
+        5 => "SIGTRAP",
+        6 => "SIGABRT",
+        7 => "SIGBUS",
+        8 => "SIGFPE",
+        9 => "SIGKILL",
+        10 => "SIGUSR1",
+        11 => "SIGSEGV",
+        12 => "SIGUSR2",
+        13 => "SIGPIPE",
+        14 => "SIGALRM",
+        15 => "SIGTERM",
+        16 => "SIGSTKFLT",
+        17 => "SIGCHLD",
+        18 => "SIGCONT",
+        19 => "SIGSTOP",
+        20 => "SIGTSTP",
+        21 => "SIGTTIN",
+        22 => "SIGTTOU",
+        23 => "SIGURG",
+        24 => "SIGXCPU",
+        25 => "SIGXFSZ",
+        26 => "SIGVTALRM",
+        27 => "SIGPROF",
+        28 => "SIGWINCH",
+        29 => "SIGIO",
+        30 => "SIGPWR",
+        31 => "SIGSYS",
+        _ => return None,
+    };
+    Some(name)
+}
+
+/// Describes an exit status, decoding `128+N` into the signal name.
+pub fn describe_status(status: i32) -> String {
+    status
+        .checked_sub(128)
+        .and_then(signal_name)
+        .map(str::to_string)
+        .unwrap_or_else(|| status.to_string())
+}
+
+/// Formats a pipeline's statuses as `0|1|0`, decoding signals.
+pub fn format_pipestatus(statuses: &[i32]) -> String {
+    statuses
+        .iter()
+        .map(|status| describe_status(*status))
+        .collect::<Vec<_>>()
+        .join("|")
+}
+
+/// Shows the exit status of the last command when it failed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StatusSegment {
+    /// Shown in front of the status.
+    pub icon: Icon,
+    /// Show every status of a failed pipeline instead of only the last one.
+    pub show_pipestatus: bool,
+    pub failure_color: NamedColor,
+    /// Used when the command was killed by a signal.
+    pub signal_color: NamedColor,
+}
+
+impl Default for StatusSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Failure,
+            show_pipestatus: true,
+            failure_color: NamedColor::Red,
+            signal_color: NamedColor::Yellow,
+        }
+    }
+}
+
+impl StatusSegment {
+    /// Renders as a pure zsh conditional (`%(?..✘ %?)`), evaluated by zsh itself.
+    /// Signal names and `$pipestatus` are not available this way.
+    pub fn render_zsh(&self) -> ZshPromptBuilder {
+        ZshPromptBuilder::new().conditional(
+            ZshCondition::ExitStatus(0),
+            ZshPromptBuilder::new(),
+            ZshPromptBuilder::new()
+                .color(self.failure_color)
+                .icon(self.icon)
+                .exit_status()
+                .end_color(),
+        )
+    }
+}
+
+impl Segment for StatusSegment {
+    fn name(&self) -> &str {
+        "status"
+    }
+
+    /// Renders the status supplied by the context. Hidden on success.
+    ///
+    /// If the context has no status, falls back to `render_zsh` so zsh decides.
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let Some(status) = ctx.status else {
+            return Some(self.render_zsh());
+        };
+        let failed_pipeline = ctx.pipestatus.len() > 1 && ctx.pipestatus.iter().any(|s| *s != 0);
+        if status == 0 && !failed_pipeline {
+            return None;
+        }
+        let text = if self.show_pipestatus && failed_pipeline {
+            format_pipestatus(&ctx.pipestatus)
+        } else {
+            describe_status(status)
+        };
+        let color = if status > 128 && signal_name(status - 128).is_some() {
+            self.signal_color
+        } else {
+            self.failure_color
+        };
+        Some(
+            ZshPromptBuilder::new()
+                .color(color)
+                .icon(self.icon)
+                .str(&text)
+                .end_color(),
+        )
+    }
+}
+
+/// Shows the number of background jobs, if any.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobsSegment {
+    pub icon: Icon,
+    /// Only show the count when there are at least this many jobs.
+    pub count_threshold: usize,
+    pub color: NamedColor,
+}
+
+impl Default for JobsSegment {
+    fn default() -> Self {
+        Self {
+            icon: Icon::Jobs,
+            count_threshold: 2,
+            color: NamedColor::Blue,
+        }
+    }
+}
+
+impl Segment for JobsSegment {
+    fn name(&self) -> &str {
+        "jobs"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        if ctx.jobs == 0 {
+            return None;
+        }
+        let mut builder = ZshPromptBuilder::new()
+            .color(self.color)
+            .icon_glyph(self.icon);
+        if ctx.jobs >= self.count_threshold {
+            builder = builder.icon_space().str(&ctx.jobs.to_string());
+        }
+        Some(builder.end_color())
+    }
+}
+
+/// Shows an indicator while zle is in vi command mode (`KEYMAP=vicmd`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeymapSegment {
+    pub vicmd_text: String,
+    pub color: NamedColor,
+}
+
+impl Default for KeymapSegment {
+    fn default() -> Self {
+        Self {
+            vicmd_text: "NORMAL".to_string(),
+            color: NamedColor::Yellow,
+        }
+    }
+}
+
+impl Segment for KeymapSegment {
+    fn name(&self) -> &str {
+        "keymap"
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        if ctx.keymap.as_deref() != Some("vicmd") {
+            return None;
+        }
+        Some(
+            ZshPromptBuilder::new()
+                .color(self.color)
+                .str(&self.vicmd_text)
+                .end_color(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe_status() {
+        assert_eq!(describe_status(1), "1");
+        assert_eq!(describe_status(130), "SIGINT");
+        assert_eq!(describe_status(137), "SIGKILL");
+        assert_eq!(describe_status(200), "200");
+        assert_eq!(describe_status(-1), "-1");
+    }
+
+    #[test]
+    fn test_format_pipestatus() {
+        assert_eq!(format_pipestatus(&[0, 1, 0]), "0|1|0");
+        assert_eq!(format_pipestatus(&[141, 0]), "SIGPIPE|0");
+    }
+
+    #[test]
+    fn test_render_from_context() {
+        let segment = StatusSegment::default();
+        let ok = PromptContext {
+            status: Some(0),
+            pipestatus: vec![0],
+            ..Default::default()
+        };
+        assert!(segment.render(&ok).is_none());
+
+        let failed = PromptContext {
+            status: Some(1),
+            ..Default::default()
+        };
+        assert_eq!(
+            segment.render(&failed).unwrap().build(),
+            "%{%F{red}%}%{%G✘%} 1%{%f%}"
+        );
+
+        let killed = PromptContext {
+            status: Some(130),
+            ..Default::default()
+        };
+        assert_eq!(
+            segment.render(&killed).unwrap().build(),
+            "%{%F{yellow}%}%{%G✘%} SIGINT%{%f%}"
+        );
+
+        let pipeline = PromptContext {
+            status: Some(0),
+            pipestatus: vec![0, 1, 0],
+            ..Default::default()
+        };
+        assert_eq!(segment.render(&pipeline).unwrap().text(), "✘ 0|1|0");
+    }
+
+    #[test]
+    fn test_render_zsh_conditional() {
+        let segment = StatusSegment::default();
+        assert_eq!(
+            segment.render_zsh().build(),
+            "%(?..%{%F{red}%}%{%G✘%} %?%{%f%})"
+        );
+        assert_eq!(
+            segment.render(&PromptContext::default()).unwrap().build(),
+            segment.render_zsh().build()
+        );
+    }
+
+    #[test]
+    fn test_jobs_and_keymap() {
+        let jobs = JobsSegment::default();
+        let mut ctx = PromptContext::default();
+        assert!(jobs.render(&ctx).is_none());
+        ctx.jobs = 1;
+        assert_eq!(jobs.render(&ctx).unwrap().text(), "✦");
+        ctx.jobs = 3;
+        assert_eq!(jobs.render(&ctx).unwrap().text(), "✦ 3");
+
+        let keymap = KeymapSegment::default();
+        assert!(keymap.render(&ctx).is_none());
+        ctx.keymap = Some("main".to_string());
+        assert!(keymap.render(&ctx).is_none());
+        ctx.keymap = Some("vicmd".to_string());
+        assert_eq!(
+            keymap.render(&ctx).unwrap().build(),
+            "%{%F{yellow}%}NORMAL%{%f%}"
+        );
+    }
+}
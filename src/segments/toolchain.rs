@@ -0,0 +1,395 @@
+//! Language toolchain segments detected from project files (Rust, Node, Go, Python).
+//!
+//! Versions are read from files only; nothing is executed and no network is needed.
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{LazyLock, Mutex};
+use std::time::Duration;
+
+use regex::Regex;
+
+use crate::builder::ZshPromptBuilder;
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::icons::Icon;
+use crate::segments::{Segment, git};
+
+/// A language whose toolchain can be detected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Language {
+    Rust,
+    Node,
+    Go,
+    Python,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::Rust,
+        Language::Node,
+        Language::Go,
+        Language::Python,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Language::Rust => "rust",
+            Language::Node => "node",
+            Language::Go => "go",
+            Language::Python => "python",
+        }
+    }
+
+    /// Files that mark a project, in the order their versions are preferred.
+    pub fn markers(&self) -> &'static [&'static str] {
+        match self {
+            Language::Rust => &["rust-toolchain.toml", "rust-toolchain", "Cargo.toml"],
+            Language::Node => &[".nvmrc", ".node-version", "package.json"],
+            Language::Go => &["go.work", "go.mod"],
+            Language::Python => &[".python-version", "pyproject.toml", "requirements.txt"],
+        }
+    }
+
+    pub fn default_icon(&self) -> Icon {
+        match self {
+            Language::Rust => Icon::Rust,
+            Language::Node => Icon::Node,
+            Language::Go => Icon::Go,
+            Language::Python => Icon::Python,
+        }
+    }
+
+    pub fn default_color(&self) -> NamedColor {
+        match self {
+            Language::Rust => NamedColor::Code256(208),
+            Language::Node => NamedColor::Green,
+            Language::Go => NamedColor::Cyan,
+            Language::Python => NamedColor::Yellow,
+        }
+    }
+}
+
+/// A detected toolchain.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Toolchain {
+    pub language: Language,
+    /// Version or version requirement, if a project file pins one.
+    pub version: Option<String>,
+    /// Directory containing the nearest marker file.
+    pub project_dir: PathBuf,
+}
+
+static RUST_CHANNEL: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*channel\s*=\s*"([^"]+)""#).unwrap());
+static RUST_VERSION: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*rust-version\s*=\s*"([^"]+)""#).unwrap());
+static NODE_ENGINE: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#""engines"\s*:\s*\{[^}]*"node"\s*:\s*"([^"]+)""#).unwrap());
+static GO_TOOLCHAIN: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r"(?m)^toolchain\s+go(\S+)").unwrap());
+static GO_DIRECTIVE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"(?m)^go\s+(\S+)").unwrap());
+static REQUIRES_PYTHON: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"(?m)^\s*requires-python\s*=\s*"([^"]+)""#).unwrap());
+
+fn capture(re: &Regex, content: &str) -> Option<String> {
+    re.captures(content)
+        .and_then(|caps| caps.get(1))
+        .map(|m| m.as_str().trim().to_string())
+        .filter(|version| !version.is_empty())
+}
+
+fn first_line(content: &str) -> Option<String> {
+    content
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+}
+
+/// Extracts a version from the content of a marker file.
+pub fn parse_version(marker: &str, content: &str) -> Option<String> {
+    match marker {
+        "rust-toolchain.toml" => capture(&RUST_CHANNEL, content),
+        "Cargo.toml" => capture(&RUST_VERSION, content),
+        "rust-toolchain" | ".nvmrc" | ".node-version" | ".python-version" => {
+            first_line(content).map(|v| v.trim_start_matches('v').to_string())
+        }
+        "package.json" => capture(&NODE_ENGINE, content),
+        // toolchain 指定があれば go ディレクティブより優先する
+        "go.mod" | "go.work" => {
+            capture(&GO_TOOLCHAIN, content).or_else(|| capture(&GO_DIRECTIVE, content))
+        }
+        "pyproject.toml" => capture(&REQUIRES_PYTHON, content),
+        _ => None,
+    }
+}
+
+/// Directories searched from `dir`: up to the repository root, and never
+/// `home` itself unless `dir` is `home`, so stray marker files there do not
+/// apply to every project below it.
+fn search_dirs<'a>(dir: &'a Path, home: Option<&Path>) -> Vec<&'a Path> {
+    let repo_root = git::find_repo_root(dir);
+    let mut dirs = Vec::new();
+    for ancestor in dir.ancestors() {
+        if home == Some(ancestor) && ancestor != dir {
+            break;
+        }
+        dirs.push(ancestor);
+        if repo_root.as_deref() == Some(ancestor) {
+            break;
+        }
+    }
+    dirs
+}
+
+/// Finds the toolchain for `language` by walking up from `dir`, stopping at
+/// the repository root or below `home`.
+///
+/// The nearest directory with any marker is the project. Its version comes
+/// from the first marker in preference order that pins one in the project or
+/// above, so a workspace-level `rust-toolchain.toml` wins over a member's
+/// `rust-version`, which is only the minimum supported version.
+pub fn detect(language: Language, dir: &Path, home: Option<&Path>) -> Option<Toolchain> {
+    let dirs = search_dirs(dir, home);
+    let project = dirs.iter().position(|ancestor| {
+        language
+            .markers()
+            .iter()
+            .any(|marker| ancestor.join(marker).is_file())
+    })?;
+    let version = language.markers().iter().find_map(|marker| {
+        dirs[project..].iter().find_map(|ancestor| {
+            let content = fs::read_to_string(ancestor.join(marker)).ok()?;
+            parse_version(marker, &content)
+        })
+    });
+    Some(Toolchain {
+        language,
+        version,
+        project_dir: dirs[project].to_path_buf(),
+    })
+}
+
+/// Working directory and home directory a detection ran with.
+type CacheKey = (PathBuf, Option<PathBuf>);
+
+/// Detects toolchains and caches the result per directory and home.
+///
+/// The cache lives in memory as long as the detector, so it only pays off
+/// when one detector renders many prompts, e.g. in a long-running process.
+/// `zsh_seq_cli render` starts fresh for every prompt and always detects.
+#[derive(Debug, Default)]
+pub struct ToolchainDetector {
+    cache: Mutex<HashMap<CacheKey, Vec<Toolchain>>>,
+}
+
+impl ToolchainDetector {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the toolchains of the given languages found from `dir`.
+    pub fn detect(
+        &self,
+        dir: &Path,
+        home: Option<&Path>,
+        languages: &[Language],
+    ) -> Vec<Toolchain> {
+        let mut cache = self.cache.lock().unwrap_or_else(|e| e.into_inner());
+        let key = (dir.to_path_buf(), home.map(Path::to_path_buf));
+        let all = cache.entry(key).or_insert_with(|| {
+            Language::ALL
+                .iter()
+                .filter_map(|language| detect(*language, dir, home))
+                .collect()
+        });
+        all.iter()
+            .filter(|toolchain| languages.contains(&toolchain.language))
+            .cloned()
+            .collect()
+    }
+
+    /// Forgets all cached results.
+    pub fn clear(&self) {
+        self.cache.lock().unwrap_or_else(|e| e.into_inner()).clear();
+    }
+}
+
+/// Shows the toolchains of the current project, e.g. `🦀 1.75 ⬢ 18`.
+#[derive(Debug, Default)]
+pub struct ToolchainSegment {
+    pub languages: Vec<Language>,
+    pub detector: ToolchainDetector,
+}
+
+impl ToolchainSegment {
+    pub fn new(languages: Vec<Language>) -> Self {
+        Self {
+            languages,
+            detector: ToolchainDetector::new(),
+        }
+    }
+}
+
+impl Segment for ToolchainSegment {
+    fn name(&self) -> &str {
+        match self.languages.as_slice() {
+            [language] => language.name(),
+            _ => "toolchain",
+        }
+    }
+
+    fn render(&self, ctx: &PromptContext) -> Option<ZshPromptBuilder> {
+        let toolchains = self
+            .detector
+            .detect(&ctx.cwd, ctx.home.as_deref(), &self.languages);
+        if toolchains.is_empty() {
+            return None;
+        }
+        let mut builder = ZshPromptBuilder::new();
+        for (i, toolchain) in toolchains.iter().enumerate() {
+            if i > 0 {
+                builder = builder.str(" ");
+            }
+            let language = toolchain.language;
+            builder = builder
+                .color(language.default_color())
+                .icon(language.default_icon())
+                .str(toolchain.version.as_deref().unwrap_or(language.name()))
+                .end_color();
+        }
+        Some(builder)
+    }
+
+    fn timeout_hint(&self) -> Option<Duration> {
+        Some(Duration::from_millis(50))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::segments::tests::temp_dir;
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(
+            parse_version("rust-toolchain.toml", "[toolchain]\nchannel = \"1.75.0\"\n").as_deref(),
+            Some("1.75.0")
+        );
+        assert_eq!(
+            parse_version(
+                "Cargo.toml",
+                "[package]\nname = \"x\"\nrust-version = \"1.70\"\n"
+            )
+            .as_deref(),
+            Some("1.70")
+        );
+        assert_eq!(
+            parse_version(".nvmrc", "v20.11.0\n").as_deref(),
+            Some("20.11.0")
+        );
+        assert_eq!(
+            parse_version(
+                "package.json",
+                r#"{"name": "x", "engines": { "npm": ">=9", "node": ">=18" }}"#
+            )
+            .as_deref(),
+            Some(">=18")
+        );
+        assert_eq!(
+            parse_version("go.mod", "module x\n\ngo 1.21\n").as_deref(),
+            Some("1.21")
+        );
+        assert_eq!(
+            parse_version("go.mod", "module x\n\ngo 1.21\ntoolchain go1.22.1\n").as_deref(),
+            Some("1.22.1")
+        );
+        assert_eq!(
+            parse_version(
+                "pyproject.toml",
+                "[project]\nrequires-python = \">=3.10\"\n"
+            )
+            .as_deref(),
+            Some(">=3.10")
+        );
+        assert_eq!(parse_version("package.json", "{}"), None);
+    }
+
+    #[test]
+    fn test_detect_walks_up_to_workspace_toolchain() {
+        let root = temp_dir("toolchain");
+        fs::write(
+            root.join("rust-toolchain.toml"),
+            "[toolchain]\nchannel = \"nightly\"\n",
+        )
+        .unwrap();
+        let member = root.join("crates/member");
+        fs::create_dir_all(member.join("src")).unwrap();
+        // rust-version は MSRV なのでワークスペースの rust-toolchain.toml を優先する
+        fs::write(
+            member.join("Cargo.toml"),
+            "[package]\nname = \"member\"\nrust-version = \"1.70\"\n",
+        )
+        .unwrap();
+
+        let toolchain = detect(Language::Rust, &member.join("src"), None).unwrap();
+        assert_eq!(toolchain.project_dir, member);
+        assert_eq!(toolchain.version.as_deref(), Some("nightly"));
+        assert_eq!(detect(Language::Go, &member, None), None);
+
+        let _ = fs::remove_dir_all(&root);
+    }
+
+    #[test]
+    fn test_detect_stops_at_home_and_repo_root() {
+        let home = temp_dir("toolchain-home");
+        fs::write(home.join(".python-version"), "3.12\n").unwrap();
+        let project = home.join("project");
+        fs::create_dir_all(&project).unwrap();
+        assert_eq!(detect(Language::Python, &project, Some(&home)), None);
+        assert!(detect(Language::Python, &home, Some(&home)).is_some());
+
+        let repo = home.join("repo");
+        fs::create_dir_all(repo.join(".git")).unwrap();
+        let sub = repo.join("sub");
+        fs::create_dir_all(&sub).unwrap();
+        assert_eq!(detect(Language::Python, &sub, None), None);
+
+        let _ = fs::remove_dir_all(&home);
+    }
+
+    #[test]
+    fn test_segment_renders_and_caches() {
+        let root = temp_dir("toolchain-segment");
+        fs::write(root.join(".nvmrc"), "18\n").unwrap();
+        fs::write(root.join("go.mod"), "module x\n\ngo 1.22\n").unwrap();
+        let segment = ToolchainSegment::new(vec![Language::Node, Language::Go]);
+        let ctx = PromptContext {
+            cwd: root.clone(),
+            ..Default::default()
+        };
+        assert_eq!(segment.render(&ctx).unwrap().text(), "⬢ 18 🐹 1.22");
+
+        // キャッシュされているのでファイルを変更しても結果は変わらない
+        fs::write(root.join(".nvmrc"), "20\n").unwrap();
+        assert_eq!(segment.render(&ctx).unwrap().text(), "⬢ 18 🐹 1.22");
+        segment.detector.clear();
+        assert_eq!(segment.render(&ctx).unwrap().text(), "⬢ 20 🐹 1.22");
+
+        // home が違えば別のエントリになる
+        let project = root.join("project");
+        fs::create_dir_all(&project).unwrap();
+        let mut ctx = PromptContext {
+            cwd: project,
+            ..Default::default()
+        };
+        assert_eq!(segment.render(&ctx).unwrap().text(), "⬢ 20 🐹 1.22");
+        ctx.home = Some(root.clone());
+        assert!(segment.render(&ctx).is_none());
+
+        let _ = fs::remove_dir_all(&root);
+    }
+}
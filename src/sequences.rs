@@ -1,5 +1,6 @@
 use crate::colors::NamedColor;
-use std::env;
+use crate::context::{self, PromptContext};
+use crate::icons::{Icon, IconSet};
 /// Represents a Zsh prompt sequence.
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum ZshSequence {
@@ -41,8 +42,144 @@ pub enum ZshSequence {
     Newline,
     /// Custom string that can be inserted directly.
     Literal(String),
+    /// Exit status of the last command (%?)
+    ExitStatus,
+    /// Icon drawn from the given set, with its cell width declared via %G
+    Icon(Icon, IconSet),
+    /// Space between an icon and its text, left out with `IconSet::None`
+    IconSpace(IconSet),
+    /// Ternary expression - generates %(x.true.false)
+    Conditional {
+        condition: ZshCondition,
+        when_true: Vec<ZshSequence>,
+        when_false: Vec<ZshSequence>,
+    },
 }
+
+/// A test usable in a `ZshSequence::Conditional`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZshCondition {
+    /// True if the last exit status equals the given value (%(n?...)).
+    /// zsh only accepts non-negative values here.
+    ExitStatus(u32),
+    /// True if the shell is running with privileges (%(!...))
+    Privileged,
+}
+
+impl ZshCondition {
+    /// Evaluates the condition against a context. An unknown status counts as 0.
+    pub fn evaluate(&self, ctx: &PromptContext) -> bool {
+        match self {
+            ZshCondition::ExitStatus(n) => i64::from(ctx.status.unwrap_or(0)) == i64::from(*n),
+            ZshCondition::Privileged => ctx.privileged,
+        }
+    }
+}
+
+impl std::fmt::Display for ZshCondition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ZshCondition::ExitStatus(0) => write!(f, "?"),
+            ZshCondition::ExitStatus(n) => write!(f, "{}?", n),
+            ZshCondition::Privileged => write!(f, "!"),
+        }
+    }
+}
+
+/// Replaces every `Conditional` with the branch selected by `ctx`, recursively.
+pub fn expand_conditionals(sequences: &[ZshSequence], ctx: &PromptContext) -> Vec<ZshSequence> {
+    let mut result = Vec::with_capacity(sequences.len());
+    for seq in sequences {
+        match seq {
+            ZshSequence::Conditional {
+                condition,
+                when_true,
+                when_false,
+            } => {
+                let branch = if condition.evaluate(ctx) {
+                    when_true
+                } else {
+                    when_false
+                };
+                result.extend(expand_conditionals(branch, ctx));
+            }
+            _ => result.push(seq.clone()),
+        }
+    }
+    result
+}
+
+/// Renders a conditional branch, escaping `)` in literal text as `%)`.
+///
+/// zsh has no escape for the separator, so where it is printed it becomes a
+/// nested conditional that prints it either way (`%(!|.|.)`); the outer
+/// conditional skips nested ones as a whole.
+fn conditional_branch(sequences: &[ZshSequence], separator: char) -> String {
+    let inner = if separator == '|' { '.' } else { '|' };
+    let escaped = format!("%(!{0}{1}{0}{1})", inner, separator);
+    sequences
+        .iter()
+        .map(|seq| match seq {
+            ZshSequence::Literal(_) => seq
+                .to_string()
+                .replace(')', "%)")
+                .replace(separator, &escaped),
+            ZshSequence::Icon(..) => seq.to_string().replace(separator, &escaped),
+            _ => seq.to_string(),
+        })
+        .collect()
+}
+
 impl ZshSequence {
+    /// Returns true for sequences that only change styles or colors and print nothing.
+    pub fn is_style(&self) -> bool {
+        matches!(
+            self,
+            ZshSequence::BoldStart
+                | ZshSequence::BoldEnd
+                | ZshSequence::UnderlineStart
+                | ZshSequence::UnderlineEnd
+                | ZshSequence::StandoutStart
+                | ZshSequence::StandoutEnd
+                | ZshSequence::ForegroundColor(_)
+                | ZshSequence::ForegroundColorEnd
+                | ZshSequence::BackgroundColor(_)
+                | ZshSequence::BackgroundColorEnd
+                | ZshSequence::ResetStyles
+        )
+    }
+
+    /// Returns true for foreground/background color sequences.
+    pub fn is_color(&self) -> bool {
+        matches!(
+            self,
+            ZshSequence::ForegroundColor(_)
+                | ZshSequence::ForegroundColorEnd
+                | ZshSequence::BackgroundColor(_)
+                | ZshSequence::BackgroundColorEnd
+        )
+    }
+
+    /// Returns the text this sequence prints, taking dynamic values from `ctx`
+    /// instead of the running process. Style sequences resolve to an empty string.
+    pub fn resolve(&self, ctx: &PromptContext) -> String {
+        match self {
+            ZshSequence::Literal(s) => s.clone(),
+            ZshSequence::Username => ctx.username.clone(),
+            ZshSequence::HostnameShort => ctx.hostname.clone(),
+            ZshSequence::CurrentDirectoryTilde => ctx.cwd_tilde(),
+            ZshSequence::CurrentDirectoryFull => ctx.cwd_full(),
+            ZshSequence::PrivilegedIndicator => if ctx.privileged { "#" } else { "%" }.to_string(),
+            ZshSequence::ExitStatus => ctx.status.unwrap_or(0).to_string(),
+            ZshSequence::Icon(icon, set) => icon.glyph(*set).to_string(),
+            ZshSequence::Conditional { .. } => expand_conditionals(std::slice::from_ref(self), ctx)
+                .iter()
+                .map(|seq| seq.resolve(ctx))
+                .collect(),
+            _ => self.raw_text(),
+        }
+    }
+
     pub fn raw_text(&self) -> String {
         match self {
             ZshSequence::Literal(s) => {
@@ -75,60 +212,39 @@ impl ZshSequence {
             ZshSequence::Percent => "%".to_string(),
 
             // ユーザー名: $USER を取得
-            ZshSequence::Username => users::get_current_username()
-                .unwrap()
-                .into_string()
-                .unwrap(),
+            ZshSequence::Username => context::current_username(),
 
             // ホスト名: hostname_short (最初のドットまで)
-            ZshSequence::HostnameShort => hostname::get()
-                .map(|h| {
-                    h.to_string_lossy()
-                        .split('.')
-                        .next()
-                        .unwrap_or("localhost")
-                        .to_string()
-                })
-                .unwrap_or_else(|_| {
-                    env::var("HOSTNAME")
-                        .map(|h| h.split('.').next().unwrap_or("localhost").to_string())
-                        .unwrap_or_else(|_| "localhost".to_string())
-                }),
+            ZshSequence::HostnameShort => context::current_hostname(),
 
             // カレントディレクトリ (Tildeあり)
             ZshSequence::CurrentDirectoryTilde => {
-                let pwd = env::current_dir().unwrap_or_else(|_| std::path::PathBuf::from("."));
-                let home = env::var("HOME").map(std::path::PathBuf::from).ok();
-
-                if let Some(h) = home
-                    && let Ok(stripped) = pwd.strip_prefix(h)
-                {
-                    return format!("~/{}", stripped.display())
-                        .trim_end_matches('/')
-                        .to_string();
-                }
-                pwd.display().to_string().trim_end_matches('/').to_string()
+                context::tilde_path(&context::current_dir(), context::home_dir().as_deref())
             }
 
             // カレントディレクトリ (Full)
-            ZshSequence::CurrentDirectoryFull => env::current_dir()
-                .map(|p| p.display().to_string().trim_end_matches('/').to_string())
-                .unwrap_or_else(|_| "/".to_string()),
+            ZshSequence::CurrentDirectoryFull => context::display_path(&context::current_dir()),
 
             // 特権インジケータ
             ZshSequence::PrivilegedIndicator => {
-                // UIDが0なら #、それ以外は % (Unix系前提)
-                if users::get_current_uid() == 0 {
-                    "#"
-                } else {
-                    "%"
-                }
-                .to_string()
+                if context::is_privileged() { "#" } else { "%" }.to_string()
             }
 
             // 改行
             ZshSequence::Newline => "\n".to_string(),
 
+            // プロセス外からは終了ステータスが分からないため 0 とみなす
+            ZshSequence::ExitStatus => "0".to_string(),
+            ZshSequence::Icon(icon, set) => icon.glyph(*set).to_string(),
+            ZshSequence::IconSpace(set) => set.space().to_string(),
+            ZshSequence::Conditional { .. } => {
+                let ctx = PromptContext {
+                    privileged: context::is_privileged(),
+                    ..Default::default()
+                };
+                self.resolve(&ctx)
+            }
+
             // スタイル・色関連はテキストとしては「空」
             ZshSequence::BoldStart
             | ZshSequence::BoldEnd
@@ -183,6 +299,38 @@ impl std::fmt::Display for ZshSequence {
                 }
                 Ok(())
             }
+            ZshSequence::ExitStatus => write!(f, "%?"),
+            ZshSequence::Icon(icon, set) => {
+                let glyph = icon.glyph(*set);
+                match icon.width(*set) {
+                    _ if glyph.is_ascii() => write!(f, "{}", glyph),
+                    1 => write!(f, "%{{%G{}%}}", glyph),
+                    width => write!(f, "%{{%{}G{}%}}", width, glyph),
+                }
+            }
+            ZshSequence::IconSpace(set) => write!(f, "{}", set.space()),
+            ZshSequence::Conditional {
+                condition,
+                when_true,
+                when_false,
+            } => {
+                // 分岐の中に現れない文字を区切りに選び、なければ '.' をエスケープする
+                let printed = |branch: &[ZshSequence]| -> String {
+                    branch.iter().map(ToString::to_string).collect()
+                };
+                let (printed_true, printed_false) = (printed(when_true), printed(when_false));
+                let separator = ['.', '|', ':', ',', ';', '^', '~']
+                    .into_iter()
+                    .find(|c| !printed_true.contains(*c) && !printed_false.contains(*c))
+                    .unwrap_or('.');
+                let when_true = conditional_branch(when_true, separator);
+                let when_false = conditional_branch(when_false, separator);
+                write!(
+                    f,
+                    "%({}{}{}{}{})",
+                    condition, separator, when_true, separator, when_false
+                )
+            }
         }
     }
 }
@@ -265,6 +413,85 @@ mod tests {
         assert_eq!(ZshSequence::PrivilegedIndicator.to_string(), "%#");
     }
 
+    #[test]
+    fn test_resolve_with_context() {
+        let ctx = PromptContext {
+            username: "alice".to_string(),
+            hostname: "box".to_string(),
+            cwd: "/home/alice/src".into(),
+            home: Some("/home/alice".into()),
+            privileged: true,
+            ..Default::default()
+        };
+        assert_eq!(ZshSequence::Username.resolve(&ctx), "alice");
+        assert_eq!(ZshSequence::HostnameShort.resolve(&ctx), "box");
+        assert_eq!(ZshSequence::CurrentDirectoryTilde.resolve(&ctx), "~/src");
+        assert_eq!(
+            ZshSequence::CurrentDirectoryFull.resolve(&ctx),
+            "/home/alice/src"
+        );
+        assert_eq!(ZshSequence::PrivilegedIndicator.resolve(&ctx), "#");
+        assert_eq!(
+            ZshSequence::Literal("100%".to_string()).resolve(&ctx),
+            "100%"
+        );
+        assert_eq!(ZshSequence::BoldStart.resolve(&ctx), "");
+    }
+
+    #[test]
+    fn test_exit_status_sequence() {
+        assert_eq!(ZshSequence::ExitStatus.to_string(), "%?");
+    }
+
+    #[test]
+    fn test_conditional_sequence() {
+        let seq = ZshSequence::Conditional {
+            condition: ZshCondition::ExitStatus(0),
+            when_true: vec![],
+            when_false: vec![
+                ZshSequence::ForegroundColor(NamedColor::Red),
+                ZshSequence::Literal("(".to_string()),
+                ZshSequence::ExitStatus,
+                ZshSequence::Literal(")".to_string()),
+                ZshSequence::ForegroundColorEnd,
+            ],
+        };
+        assert_eq!(seq.to_string(), "%(?..%{%F{red}%}(%?%)%{%f%})");
+
+        let seq = ZshSequence::Conditional {
+            condition: ZshCondition::Privileged,
+            when_true: vec![ZshSequence::Literal("a.b".to_string())],
+            when_false: vec![ZshSequence::Literal("c".to_string())],
+        };
+        assert_eq!(seq.to_string(), "%(!|a.b|c)");
+
+        // 候補がすべて使われているときは '.' を入れ子の条件式で出す
+        let seq = ZshSequence::Conditional {
+            condition: ZshCondition::ExitStatus(1),
+            when_true: vec![ZshSequence::Literal("a.|:,;^~)".to_string())],
+            when_false: vec![ZshSequence::Literal("c".to_string())],
+        };
+        assert_eq!(seq.to_string(), "%(1?.a%(!|.|.)|:,;^~%).c)");
+    }
+
+    #[test]
+    fn test_conditional_resolve() {
+        let seq = ZshSequence::Conditional {
+            condition: ZshCondition::ExitStatus(0),
+            when_true: vec![ZshSequence::Literal("ok".to_string())],
+            when_false: vec![
+                ZshSequence::Literal("err ".to_string()),
+                ZshSequence::ExitStatus,
+            ],
+        };
+        let failed = PromptContext {
+            status: Some(2),
+            ..Default::default()
+        };
+        assert_eq!(seq.resolve(&PromptContext::default()), "ok");
+        assert_eq!(seq.resolve(&failed), "err 2");
+    }
+
     #[test]
     fn test_literal_sequence() {
         assert_eq!(
@@ -282,4 +509,28 @@ mod tests {
             "%{%Gあ%}"
         );
     }
+
+    #[test]
+    fn test_icon_sequence() {
+        assert_eq!(
+            ZshSequence::Icon(Icon::Kubernetes, IconSet::Unicode).to_string(),
+            "%{%G☸%}"
+        );
+        assert_eq!(
+            ZshSequence::Icon(Icon::Python, IconSet::Unicode).to_string(),
+            "%{%2G🐍%}"
+        );
+        assert_eq!(
+            ZshSequence::Icon(Icon::Python, IconSet::NerdFont).to_string(),
+            "%{%G\u{e73c}%}"
+        );
+        assert_eq!(
+            ZshSequence::Icon(Icon::Python, IconSet::Ascii).to_string(),
+            "py"
+        );
+        assert_eq!(
+            ZshSequence::Icon(Icon::Python, IconSet::None).to_string(),
+            ""
+        );
+    }
 }
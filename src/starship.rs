@@ -0,0 +1,477 @@
+//! Parser for starship `format` strings.
+//!
+//! Supports `$variable` / `${variable}`, text groups `[text](style)`,
+//! conditional groups `(...)` and backslash escapes. Variables name segments in
+//! a `SegmentRegistry`; starship module names are mapped to the builtin
+//! segments (`$git_branch` → `git`, `$nodejs` → `node`, ...).
+
+use std::fmt;
+
+use crate::colors::NamedColor;
+use crate::context::PromptContext;
+use crate::segments::SegmentRegistry;
+use crate::sequences::{ZshCondition, ZshSequence};
+
+/// Error for a malformed format string. `column` counts characters from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormatError {
+    pub message: String,
+    pub column: usize,
+}
+
+impl fmt::Display for FormatError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "column {}: {}", self.column, self.message)
+    }
+}
+
+impl std::error::Error for FormatError {}
+
+/// Styles that have a zsh prompt equivalent. `italic`, `dimmed`, `blink`,
+/// `hidden` and `strikethrough` are accepted but dropped.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Style {
+    pub fg: Option<NamedColor>,
+    pub bg: Option<NamedColor>,
+    pub bold: bool,
+    pub underline: bool,
+    pub inverted: bool,
+}
+
+impl Style {
+    /// Parses a starship style string such as `bold fg:#ff8800 bg:blue`.
+    pub fn parse(style: &str) -> Result<Self, String> {
+        let mut result = Style::default();
+        for token in style.split_whitespace() {
+            let lower = token.to_ascii_lowercase();
+            match lower.as_str() {
+                "bold" => result.bold = true,
+                "underline" => result.underline = true,
+                "inverted" => result.inverted = true,
+                "italic" | "dimmed" | "blink" | "hidden" | "strikethrough" => {}
+                "none" => result = Style::default(),
+                _ => {
+                    if let Some(color) = lower.strip_prefix("bg:") {
+                        result.bg = Some(parse_color(color)?);
+                    } else {
+                        let color = lower.strip_prefix("fg:").unwrap_or(&lower);
+                        result.fg = Some(parse_color(color)?);
+                    }
+                }
+            }
+        }
+        Ok(result)
+    }
+
+    /// This style with the unset parts taken from `parent`.
+    fn inherit(&self, parent: &Style) -> Style {
+        Style {
+            fg: self.fg.or(parent.fg),
+            bg: self.bg.or(parent.bg),
+            bold: self.bold || parent.bold,
+            underline: self.underline || parent.underline,
+            inverted: self.inverted || parent.inverted,
+        }
+    }
+
+    fn start(&self, out: &mut Vec<ZshSequence>) {
+        if let Some(bg) = self.bg {
+            out.push(ZshSequence::BackgroundColor(bg));
+        }
+        if let Some(fg) = self.fg {
+            out.push(ZshSequence::ForegroundColor(fg));
+        }
+        if self.bold {
+            out.push(ZshSequence::BoldStart);
+        }
+        if self.underline {
+            out.push(ZshSequence::UnderlineStart);
+        }
+        if self.inverted {
+            out.push(ZshSequence::StandoutStart);
+        }
+    }
+
+    fn end(&self, out: &mut Vec<ZshSequence>) {
+        if self.inverted {
+            out.push(ZshSequence::StandoutEnd);
+        }
+        if self.underline {
+            out.push(ZshSequence::UnderlineEnd);
+        }
+        if self.bold {
+            out.push(ZshSequence::BoldEnd);
+        }
+        if self.fg.is_some() {
+            out.push(ZshSequence::ForegroundColorEnd);
+        }
+        if self.bg.is_some() {
+            out.push(ZshSequence::BackgroundColorEnd);
+        }
+    }
+}
+
+fn parse_color(color: &str) -> Result<NamedColor, String> {
+    // starship は magenta を purple と呼ぶ
+    let color = color.replace("purple", "magenta");
+    color.parse().map_err(|err| format!("{}", err))
+}
+
+/// A parsed piece of a format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FormatNode {
+    Text(String),
+    Variable(String),
+    /// `[children](style)`
+    Styled {
+        children: Vec<FormatNode>,
+        style: Style,
+    },
+    /// `(children)`, shown only if a variable inside is non-empty.
+    Conditional(Vec<FormatNode>),
+}
+
+/// A parsed starship format string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StarshipFormat {
+    pub nodes: Vec<FormatNode>,
+}
+
+struct Parser {
+    chars: Vec<char>,
+    pos: usize,
+}
+
+impl Parser {
+    fn error(&self, column: usize, message: impl Into<String>) -> FormatError {
+        FormatError {
+            message: message.into(),
+            column: column + 1,
+        }
+    }
+
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.pos).copied()
+    }
+
+    /// Parses nodes until `close` (consumed) or the end of input when `close` is `None`.
+    fn nodes(&mut self, close: Option<(char, usize)>) -> Result<Vec<FormatNode>, FormatError> {
+        let mut nodes = Vec::new();
+        let mut text = String::new();
+        let flush = |text: &mut String, nodes: &mut Vec<FormatNode>| {
+            if !text.is_empty() {
+                nodes.push(FormatNode::Text(std::mem::take(text)));
+            }
+        };
+        while let Some(c) = self.peek() {
+            let start = self.pos;
+            self.pos += 1;
+            match c {
+                '\\' => match self.peek() {
+                    Some(escaped) => {
+                        text.push(escaped);
+                        self.pos += 1;
+                    }
+                    None => return Err(self.error(start, "trailing backslash")),
+                },
+                '$' => {
+                    flush(&mut text, &mut nodes);
+                    nodes.push(FormatNode::Variable(self.variable(start)?));
+                }
+                '[' => {
+                    flush(&mut text, &mut nodes);
+                    let children = self.nodes(Some((']', start)))?;
+                    let style = self.style()?;
+                    nodes.push(FormatNode::Styled { children, style });
+                }
+                '(' => {
+                    flush(&mut text, &mut nodes);
+                    let children = self.nodes(Some((')', start)))?;
+                    nodes.push(FormatNode::Conditional(children));
+                }
+                c if close.is_some_and(|(close, _)| close == c) => {
+                    flush(&mut text, &mut nodes);
+                    return Ok(nodes);
+                }
+                ']' | ')' => return Err(self.error(start, format!("unmatched `{}`", c))),
+                c => text.push(c),
+            }
+        }
+        if let Some((close, open)) = close {
+            return Err(self.error(open, format!("missing closing `{}`", close)));
+        }
+        flush(&mut text, &mut nodes);
+        Ok(nodes)
+    }
+
+    fn variable(&mut self, start: usize) -> Result<String, FormatError> {
+        let braced = self.peek() == Some('{');
+        if braced {
+            self.pos += 1;
+        }
+        let name_start = self.pos;
+        while self
+            .peek()
+            .is_some_and(|c| c.is_ascii_alphanumeric() || c == '_')
+        {
+            self.pos += 1;
+        }
+        let name: String = self.chars[name_start..self.pos].iter().collect();
+        if name.is_empty() {
+            return Err(self.error(start, "expected a variable name after `$`"));
+        }
+        if braced {
+            if self.peek() != Some('}') {
+                return Err(self.error(start, "missing closing `}`"));
+            }
+            self.pos += 1;
+        }
+        Ok(name)
+    }
+
+    /// Parses the `(style)` following a text group; it may be omitted.
+    fn style(&mut self) -> Result<Style, FormatError> {
+        if self.peek() != Some('(') {
+            return Ok(Style::default());
+        }
+        let open = self.pos;
+        self.pos += 1;
+        let style_start = self.pos;
+        while self.peek().is_some_and(|c| c != ')') {
+            self.pos += 1;
+        }
+        if self.peek().is_none() {
+            return Err(self.error(open, "missing closing `)`"));
+        }
+        let style: String = self.chars[style_start..self.pos].iter().collect();
+        self.pos += 1;
+        // $style などモジュール側の変数は解決できないので無視する
+        if style.contains('$') {
+            return Ok(Style::default());
+        }
+        Style::parse(&style).map_err(|message| self.error(style_start, message))
+    }
+}
+
+/// Starship module names that differ from the builtin segment names.
+/// `None` means the module is already covered by another segment.
+fn segment_name(variable: &str) -> Option<&str> {
+    match variable {
+        "git_branch" => Some("git"),
+        "git_commit" | "git_state" | "git_status" | "git_metrics" => None,
+        "cmd_duration" => Some("duration"),
+        "nodejs" => Some("node"),
+        "golang" => Some("go"),
+        "docker_context" => Some("docker"),
+        other => Some(other),
+    }
+}
+
+impl StarshipFormat {
+    pub fn parse(format: &str) -> Result<Self, FormatError> {
+        let mut parser = Parser {
+            chars: format.chars().collect(),
+            pos: 0,
+        };
+        Ok(Self {
+            nodes: parser.nodes(None)?,
+        })
+    }
+
+    /// Names of all variables used, in order of appearance.
+    pub fn variables(&self) -> Vec<&str> {
+        fn collect<'a>(nodes: &'a [FormatNode], out: &mut Vec<&'a str>) {
+            for node in nodes {
+                match node {
+                    FormatNode::Variable(name) => out.push(name),
+                    FormatNode::Styled { children, .. } | FormatNode::Conditional(children) => {
+                        collect(children, out)
+                    }
+                    FormatNode::Text(_) => {}
+                }
+            }
+        }
+        let mut out = Vec::new();
+        collect(&self.nodes, &mut out);
+        out
+    }
+
+    /// Renders the format, resolving variables through `registry`.
+    ///
+    /// `$line_break` and `$character` are handled here; variables that name no
+    /// segment render as empty, like a disabled starship module.
+    pub fn to_sequences(
+        &self,
+        registry: &SegmentRegistry,
+        ctx: &PromptContext,
+    ) -> Vec<ZshSequence> {
+        let mut out = Vec::new();
+        render_nodes(&self.nodes, &Style::default(), registry, ctx, &mut out);
+        out
+    }
+}
+
+/// Renders `nodes` into `out`, returning whether any variable produced output.
+fn render_nodes(
+    nodes: &[FormatNode],
+    current: &Style,
+    registry: &SegmentRegistry,
+    ctx: &PromptContext,
+    out: &mut Vec<ZshSequence>,
+) -> bool {
+    let mut any_value = false;
+    for node in nodes {
+        match node {
+            FormatNode::Text(text) => out.push(ZshSequence::Literal(text.clone())),
+            FormatNode::Variable(name) => {
+                let rendered = render_variable(name, registry, ctx);
+                if rendered.is_empty() {
+                    continue;
+                }
+                any_value = true;
+                let styled = rendered.iter().any(ZshSequence::is_style);
+                out.extend(rendered);
+                // セグメントが自分の色を閉じた後に外側のスタイルを戻す
+                if styled {
+                    current.start(out);
+                }
+            }
+            FormatNode::Styled { children, style } => {
+                style.start(out);
+                any_value |= render_nodes(children, &style.inherit(current), registry, ctx, out);
+                style.end(out);
+                current.start(out);
+            }
+            FormatNode::Conditional(children) => {
+                let mut inner = Vec::new();
+                let shown = render_nodes(children, current, registry, ctx, &mut inner);
+                if shown {
+                    any_value = true;
+                    out.extend(inner);
+                }
+            }
+        }
+    }
+    any_value
+}
+
+fn render_variable(
+    name: &str,
+    registry: &SegmentRegistry,
+    ctx: &PromptContext,
+) -> Vec<ZshSequence> {
+    match name {
+        "line_break" => return vec![ZshSequence::Newline],
+        "character" => {
+            let prompt = |color| {
+                vec![
+                    ZshSequence::ForegroundColor(color),
+                    ZshSequence::Literal("❯".to_string()),
+                    ZshSequence::ForegroundColorEnd,
+                ]
+            };
+            return vec![ZshSequence::Conditional {
+                condition: ZshCondition::ExitStatus(0),
+                when_true: prompt(NamedColor::Green),
+                when_false: prompt(NamedColor::Red),
+            }];
+        }
+        _ => {}
+    }
+    segment_name(name)
+        .and_then(|name| registry.get(name))
+        .and_then(|segment| segment.render(ctx))
+        .map(|builder| builder.sequences().to_vec())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::builder::ZshPromptBuilder;
+
+    fn render(format: &str, ctx: &PromptContext) -> String {
+        let sequences = StarshipFormat::parse(format)
+            .unwrap()
+            .to_sequences(&SegmentRegistry::with_builtins(), ctx);
+        ZshPromptBuilder::new().chain(sequences).build()
+    }
+
+    #[test]
+    fn test_parse() {
+        let format =
+            StarshipFormat::parse(r"$username@${hostname} [\[$git_branch\]](bold red)($python)")
+                .unwrap();
+        assert_eq!(
+            format.variables(),
+            ["username", "hostname", "git_branch", "python"]
+        );
+        assert_eq!(
+            format.nodes[4],
+            FormatNode::Styled {
+                children: vec![
+                    FormatNode::Text("[".to_string()),
+                    FormatNode::Variable("git_branch".to_string()),
+                    FormatNode::Text("]".to_string()),
+                ],
+                style: Style {
+                    fg: Some(NamedColor::Red),
+                    bold: true,
+                    ..Default::default()
+                },
+            }
+        );
+    }
+
+    #[test]
+    fn test_style() {
+        assert_eq!(
+            Style::parse("fg:#ff8800 bg:bright-purple underline italic"),
+            Ok(Style {
+                fg: Some(NamedColor::FullColor((255, 136, 0))),
+                bg: Some(NamedColor::LightMagenta),
+                underline: true,
+                ..Default::default()
+            })
+        );
+        assert!(Style::parse("bold chartreuse").is_err());
+    }
+
+    #[test]
+    fn test_render() {
+        let ctx = PromptContext::default();
+        assert_eq!(
+            render("$username@$hostname [>](bold green) ", &ctx),
+            "%n@%m %{%F{green}%}%{%B%}>%{%b%}%{%f%} "
+        );
+        // 入れ子のグループの後は外側の色に戻す
+        assert_eq!(
+            render("[a[b](red)c](blue)", &ctx),
+            "%{%F{blue}%}a%{%F{red}%}b%{%f%}%{%F{blue}%}c%{%f%}"
+        );
+        assert_eq!(
+            render("$line_break$character", &ctx),
+            "\n%(?.%{%F{green}%}%{%G❯%}%{%f%}.%{%F{red}%}%{%G❯%}%{%f%})"
+        );
+    }
+
+    #[test]
+    fn test_conditional_groups() {
+        let ctx = PromptContext::default();
+        assert_eq!(render("a( via $python)b", &ctx), "ab");
+        assert_eq!(render("a( on $username)b", &ctx), "a on %nb");
+        assert_eq!(render("($time)", &ctx), "");
+    }
+
+    #[test]
+    fn test_errors() {
+        let err = StarshipFormat::parse("abc [x](bold").unwrap_err();
+        assert_eq!(err.column, 8);
+        let err = StarshipFormat::parse("[x](bold mauve)").unwrap_err();
+        assert_eq!(err.column, 5);
+        let err = StarshipFormat::parse("($python").unwrap_err();
+        assert_eq!(err.to_string(), "column 1: missing closing `)`");
+        assert!(StarshipFormat::parse("a]").is_err());
+        assert!(StarshipFormat::parse("$ x").is_err());
+    }
+}
@@ -0,0 +1,162 @@
+use crate::colors::{NamedColor, WCAG_AA_CONTRAST};
+use crate::sequences::ZshSequence;
+
+/// Options controlling which checks `ZshPromptBuilder::validate` performs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ValidationOptions {
+    /// Minimum WCAG contrast ratio between foreground and background.
+    /// `None` disables the contrast check.
+    pub min_contrast_ratio: Option<f64>,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            min_contrast_ratio: Some(WCAG_AA_CONTRAST),
+        }
+    }
+}
+
+/// A problem found while validating a prompt.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ValidationIssue {
+    /// A visible span whose foreground/background pair is below the contrast threshold.
+    /// `index` points at the first sequence of the span.
+    LowContrast {
+        index: usize,
+        fg: NamedColor,
+        bg: NamedColor,
+        ratio: f64,
+    },
+}
+
+impl std::fmt::Display for ValidationIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ValidationIssue::LowContrast {
+                index,
+                fg,
+                bg,
+                ratio,
+            } => write!(
+                f,
+                "sequence {}: {} on {} has a contrast ratio of {:.2}",
+                index, fg, bg, ratio
+            ),
+        }
+    }
+}
+
+/// Runs all enabled checks over a sequence list.
+pub fn validate(sequences: &[ZshSequence], options: &ValidationOptions) -> Vec<ValidationIssue> {
+    let mut issues = Vec::new();
+    if let Some(threshold) = options.min_contrast_ratio {
+        check_contrast(sequences, threshold, &mut issues);
+    }
+    issues
+}
+
+fn check_contrast(sequences: &[ZshSequence], threshold: f64, issues: &mut Vec<ValidationIssue>) {
+    let mut fg: Option<NamedColor> = None;
+    let mut bg: Option<NamedColor> = None;
+    // 同じ色の組み合わせが続く間は一度だけ報告する
+    let mut reported = false;
+
+    for (index, seq) in sequences.iter().enumerate() {
+        match seq {
+            ZshSequence::ForegroundColor(color) => {
+                reported &= fg == Some(*color);
+                fg = Some(*color);
+            }
+            ZshSequence::BackgroundColor(color) => {
+                reported &= bg == Some(*color);
+                bg = Some(*color);
+            }
+            ZshSequence::ForegroundColorEnd => {
+                fg = None;
+                reported = false;
+            }
+            ZshSequence::BackgroundColorEnd => {
+                bg = None;
+                reported = false;
+            }
+            ZshSequence::ResetStyles => {
+                fg = None;
+                bg = None;
+                reported = false;
+            }
+            _ if reported || seq.is_style() => {}
+            _ => {
+                // 端末の既定色は不明なので、両方が明示されている場合のみ判定する
+                if let (Some(fg), Some(bg)) = (fg, bg) {
+                    let ratio = fg.contrast_ratio(&bg);
+                    if ratio < threshold {
+                        issues.push(ValidationIssue::LowContrast {
+                            index,
+                            fg,
+                            bg,
+                            ratio,
+                        });
+                        reported = true;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_low_contrast_span_is_reported_once() {
+        let sequences = vec![
+            ZshSequence::BackgroundColor(NamedColor::LightWhite),
+            ZshSequence::ForegroundColor(NamedColor::LightYellow),
+            ZshSequence::Literal("warn".to_string()),
+            ZshSequence::Username,
+            ZshSequence::ForegroundColorEnd,
+            ZshSequence::Literal(" ok".to_string()),
+        ];
+        let issues = validate(&sequences, &ValidationOptions::default());
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            issues[0],
+            ValidationIssue::LowContrast {
+                index: 2,
+                fg: NamedColor::LightYellow,
+                bg: NamedColor::LightWhite,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn test_readable_and_unset_colors_pass() {
+        let sequences = vec![
+            ZshSequence::ForegroundColor(NamedColor::LightYellow),
+            ZshSequence::Literal("no background".to_string()),
+            ZshSequence::BackgroundColor(NamedColor::Black),
+            ZshSequence::Literal("readable".to_string()),
+        ];
+        assert!(validate(&sequences, &ValidationOptions::default()).is_empty());
+    }
+
+    #[test]
+    fn test_threshold_is_configurable() {
+        let sequences = vec![
+            ZshSequence::BackgroundColor(NamedColor::Blue),
+            ZshSequence::ForegroundColor(NamedColor::Red),
+            ZshSequence::Literal("x".to_string()),
+        ];
+        let strict = ValidationOptions {
+            min_contrast_ratio: Some(21.0),
+        };
+        let disabled = ValidationOptions {
+            min_contrast_ratio: None,
+        };
+        assert_eq!(validate(&sequences, &strict).len(), 1);
+        assert!(validate(&sequences, &disabled).is_empty());
+    }
+}